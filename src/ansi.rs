@@ -0,0 +1,406 @@
+// src/ansi.rs
+//
+// ANSI escape sequences in tool output.
+//
+// Builds run with colour forced on (see `bitcoin_env` / `cargo_env`), so
+// log text carries SGR sequences such as `ESC[1;31m`.  This module:
+//   • `spans`           — splits a line into runs of text with their style,
+//                         for the GUI and terminal log panels.
+//   • `strip`           — removes every escape sequence, for error
+//                         classification, search and plain log files.
+//   • `strip_controls`  — removes all but colour sequences, for output as
+//                         it is read.
+//   • `incomplete_tail` — finds a sequence cut off at the end of a read
+//                         chunk so `drain_reader` can hold it back.
+//
+// Each line is parsed on its own: a style left open at the end of a line
+// does not carry into the next one.  Compilers, cmake and cargo all reset
+// before the newline, and it lets the log view render any line in
+// isolation.
+
+use std::borrow::Cow;
+
+const ESC: char = '\x1b';
+/// An escape sequence longer than this is malformed; stop holding it back.
+const MAX_SEQUENCE: usize = 64;
+
+// ─── Style ────────────────────────────────────────────────────────────────────
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Color {
+    /// xterm 256-colour palette index.
+    Indexed(u8),
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    /// Concrete colour, using a palette that reads well on a dark background.
+    #[must_use]
+    pub fn to_rgb(self) -> (u8, u8, u8) {
+        const BASE: [(u8, u8, u8); 16] = [
+            (0, 0, 0),
+            (205, 49, 49),
+            (13, 188, 121),
+            (229, 229, 16),
+            (36, 114, 200),
+            (188, 63, 188),
+            (17, 168, 205),
+            (229, 229, 229),
+            (102, 102, 102),
+            (241, 76, 76),
+            (35, 209, 139),
+            (245, 245, 67),
+            (59, 142, 234),
+            (214, 112, 214),
+            (41, 184, 219),
+            (255, 255, 255),
+        ];
+        match self {
+            Color::Rgb(r, g, b) => (r, g, b),
+            Color::Indexed(i @ 0..=15) => BASE[usize::from(i)],
+            Color::Indexed(i @ 16..=231) => {
+                let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+                let i = i - 16;
+                (level(i / 36), level(i / 6 % 6), level(i % 6))
+            }
+            Color::Indexed(i) => {
+                let v = 8 + (i - 232) * 10;
+                (v, v, v)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Style {
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+}
+
+impl Style {
+    /// Apply the parameters of one `ESC[…m` sequence.
+    fn apply_sgr(&mut self, params: &str) {
+        let mut codes = params
+            .split([';', ':'])
+            .map(|p| p.parse::<u16>().unwrap_or(0));
+        // An empty parameter (as in `ESC[m`) parses as 0, a reset.
+        let mut next = codes.next();
+        while let Some(code) = next {
+            match code {
+                0 => *self = Style::default(),
+                1 => self.bold = true,
+                3 => self.italic = true,
+                4 => self.underline = true,
+                22 => self.bold = false,
+                23 => self.italic = false,
+                24 => self.underline = false,
+                30..=37 => self.fg = Some(Color::Indexed((code - 30) as u8)),
+                39 => self.fg = None,
+                40..=47 => self.bg = Some(Color::Indexed((code - 40) as u8)),
+                49 => self.bg = None,
+                90..=97 => self.fg = Some(Color::Indexed((code - 90 + 8) as u8)),
+                100..=107 => self.bg = Some(Color::Indexed((code - 100 + 8) as u8)),
+                38 | 48 => {
+                    let color = match codes.next() {
+                        Some(5) => codes.next().map(|n| Color::Indexed(n as u8)),
+                        Some(2) => {
+                            let mut c = || codes.next().unwrap_or(0) as u8;
+                            Some(Color::Rgb(c(), c(), c()))
+                        }
+                        _ => None,
+                    };
+                    if code == 38 {
+                        self.fg = color;
+                    } else {
+                        self.bg = color;
+                    }
+                }
+                _ => {}
+            }
+            next = codes.next();
+        }
+    }
+}
+
+// ─── Parsing ──────────────────────────────────────────────────────────────────
+
+/// One escape sequence starting at `ESC`: its length in bytes and, for
+/// SGR sequences, the parameter string.  `None` if it is cut off.
+fn sequence(s: &str) -> Option<(usize, Option<&str>)> {
+    let bytes = s.as_bytes();
+    match bytes.get(1)? {
+        // CSI: parameters and intermediates, then one final byte.
+        b'[' => {
+            let end = bytes[2..].iter().position(|b| (0x40..=0x7e).contains(b))? + 2;
+            let sgr = (bytes[end] == b'm').then(|| &s[2..end]);
+            Some((end + 1, sgr))
+        }
+        // OSC (e.g. terminal title, hyperlinks): ends at BEL or ESC \.
+        b']' => {
+            let rest = &bytes[2..];
+            let end = rest.iter().enumerate().find_map(|(i, &b)| match b {
+                0x07 => Some(i + 1),
+                0x1b if rest.get(i + 1) == Some(&b'\\') => Some(i + 2),
+                _ => None,
+            })?;
+            Some((end + 2, None))
+        }
+        // Any intermediates (as in `ESC ( B`, which `tput sgr0` emits),
+        // then one final character.
+        _ => {
+            let intermediates = bytes[1..]
+                .iter()
+                .take_while(|b| (0x20..=0x2f).contains(*b))
+                .count();
+            let len = s[1 + intermediates..].chars().next()?.len_utf8();
+            Some((1 + intermediates + len, None))
+        }
+    }
+}
+
+/// Split `line` into `(text, style)` runs.  Escape sequences are consumed;
+/// empty runs are omitted.
+#[must_use]
+pub fn spans(line: &str) -> Vec<(&str, Style)> {
+    let mut out = Vec::new();
+    let mut style = Style::default();
+    let mut rest = line;
+
+    while let Some(pos) = rest.find(ESC) {
+        if pos > 0 {
+            out.push((&rest[..pos], style));
+        }
+        match sequence(&rest[pos..]) {
+            Some((len, sgr)) => {
+                if let Some(params) = sgr {
+                    style.apply_sgr(params);
+                }
+                rest = &rest[pos + len..];
+            }
+            // A truncated sequence at the end of the line: drop it.
+            None => return out,
+        }
+    }
+    if !rest.is_empty() {
+        out.push((rest, style));
+    }
+    out
+}
+
+/// `s` without any escape sequences.
+#[must_use]
+pub fn strip(s: &str) -> Cow<'_, str> {
+    if !s.contains(ESC) {
+        return Cow::Borrowed(s);
+    }
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(pos) = rest.find(ESC) {
+        out.push_str(&rest[..pos]);
+        match sequence(&rest[pos..]) {
+            Some((len, _)) => rest = &rest[pos + len..],
+            None => return Cow::Owned(out),
+        }
+    }
+    out.push_str(rest);
+    Cow::Owned(out)
+}
+
+/// `s` without escape sequences other than colour / style (SGR): cursor
+/// movement, line erasing and window titles mean nothing in a log.
+#[must_use]
+pub fn strip_controls(s: &str) -> Cow<'_, str> {
+    if !s.contains(ESC) {
+        return Cow::Borrowed(s);
+    }
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(pos) = rest.find(ESC) {
+        out.push_str(&rest[..pos]);
+        match sequence(&rest[pos..]) {
+            Some((len, sgr)) => {
+                if sgr.is_some() {
+                    out.push_str(&rest[pos..pos + len]);
+                }
+                rest = &rest[pos + len..];
+            }
+            None => return Cow::Owned(out),
+        }
+    }
+    out.push_str(rest);
+    Cow::Owned(out)
+}
+
+/// Byte offset at which an escape sequence that is still incomplete at the
+/// end of `bytes` starts, or `bytes.len()` if there is none.
+#[must_use]
+pub fn incomplete_tail(bytes: &[u8]) -> usize {
+    let window = bytes.len().saturating_sub(MAX_SEQUENCE);
+    let Some(esc) = bytes[window..].iter().rposition(|&b| b == 0x1b) else {
+        return bytes.len();
+    };
+    let start = window + esc;
+    let tail = String::from_utf8_lossy(&bytes[start..]);
+    match sequence(&tail) {
+        Some(_) => bytes.len(),
+        None => start,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: Option<Color> = Some(Color::Indexed(1));
+
+    fn styled(fg: Option<Color>, bold: bool) -> Style {
+        Style {
+            fg,
+            bold,
+            ..Style::default()
+        }
+    }
+
+    /// The style of the text after `sgr`.
+    fn style_of(sgr: &str) -> Style {
+        let line = format!("{sgr}x");
+        match spans(&line).as_slice() {
+            [("x", style)] => *style,
+            other => panic!("{sgr:?} gave {other:?}"),
+        }
+    }
+
+    #[test]
+    fn spans_split_at_style_changes() {
+        assert_eq!(spans(""), vec![]);
+        assert_eq!(spans("plain"), vec![("plain", Style::default())]);
+        assert_eq!(
+            spans("\x1b[1;31merror\x1b[0m: expected ';'"),
+            vec![
+                ("error", styled(RED, true)),
+                (": expected ';'", Style::default()),
+            ]
+        );
+        // Styles accumulate until reset; `ESC[m` is a reset too.
+        assert_eq!(
+            spans("\x1b[1ma\x1b[31mb\x1b[mc"),
+            vec![
+                ("a", styled(None, true)),
+                ("b", styled(RED, true)),
+                ("c", Style::default()),
+            ]
+        );
+    }
+
+    #[test]
+    fn sgr_codes() {
+        assert_eq!(style_of("\x1b[33m").fg, Some(Color::Indexed(3)));
+        assert_eq!(style_of("\x1b[93m").fg, Some(Color::Indexed(11)));
+        assert_eq!(style_of("\x1b[41m").bg, RED);
+        assert_eq!(style_of("\x1b[104m").bg, Some(Color::Indexed(12)));
+        assert!(style_of("\x1b[3m").italic);
+        assert!(style_of("\x1b[4m").underline);
+        assert_eq!(
+            style_of("\x1b[1;3;4;31;41;22;23;24;39;49m"),
+            Style::default()
+        );
+    }
+
+    #[test]
+    fn extended_colours() {
+        assert_eq!(style_of("\x1b[38;5;208m").fg, Some(Color::Indexed(208)));
+        assert_eq!(style_of("\x1b[48;5;17m").bg, Some(Color::Indexed(17)));
+        assert_eq!(
+            style_of("\x1b[38;2;255;128;0m").fg,
+            Some(Color::Rgb(255, 128, 0))
+        );
+        assert_eq!(
+            style_of("\x1b[48:2:10:20:30m").bg,
+            Some(Color::Rgb(10, 20, 30))
+        );
+        // Followed by more codes in the same sequence.
+        assert_eq!(
+            style_of("\x1b[38;5;196;1m"),
+            styled(Some(Color::Indexed(196)), true)
+        );
+        // An incomplete extended colour sets none.
+        assert_eq!(style_of("\x1b[31;38;5m").fg, None);
+    }
+
+    #[test]
+    fn unsupported_codes_are_ignored() {
+        // Dim, blink, reverse and an unknown colour space.
+        assert_eq!(style_of("\x1b[2;5;7m"), Style::default());
+        assert_eq!(style_of("\x1b[1;5m"), styled(None, true));
+        assert_eq!(style_of("\x1b[38;7;1m").fg, None);
+    }
+
+    #[test]
+    fn palette() {
+        assert_eq!(Color::Indexed(1).to_rgb(), (205, 49, 49));
+        assert_eq!(Color::Indexed(16).to_rgb(), (0, 0, 0));
+        assert_eq!(Color::Indexed(208).to_rgb(), (255, 135, 0));
+        assert_eq!(Color::Indexed(231).to_rgb(), (255, 255, 255));
+        assert_eq!(Color::Indexed(232).to_rgb(), (8, 8, 8));
+        assert_eq!(Color::Indexed(255).to_rgb(), (238, 238, 238));
+        assert_eq!(Color::Rgb(1, 2, 3).to_rgb(), (1, 2, 3));
+    }
+
+    #[test]
+    fn truncated_sequences_are_dropped() {
+        assert_eq!(spans("ok \x1b[1;3"), vec![("ok ", Style::default())]);
+        assert_eq!(strip("ok \x1b"), "ok ");
+        assert_eq!(strip("ok \x1b["), "ok ");
+        assert_eq!(strip("ok \x1b]0;title"), "ok ");
+        assert_eq!(strip_controls("ok \x1b[38;5"), "ok ");
+    }
+
+    #[test]
+    fn strip_removes_every_sequence() {
+        assert!(matches!(strip("plain"), Cow::Borrowed("plain")));
+        assert_eq!(strip("\x1b[1;31merror\x1b[0m: bad"), "error: bad");
+        assert_eq!(strip("\x1b[2K\x1b[1G50%"), "50%");
+        assert_eq!(strip("\x1b]0;title\x07done"), "done");
+        assert_eq!(strip("\x1b(B\x1b[mreset"), "reset");
+    }
+
+    #[test]
+    fn strip_controls_keeps_only_colour() {
+        assert!(matches!(strip_controls("plain"), Cow::Borrowed("plain")));
+        assert_eq!(
+            strip_controls("\x1b[2K\x1b[1G\x1b[32m50%\x1b[0m"),
+            "\x1b[32m50%\x1b[0m"
+        );
+        // Window titles (BEL or ESC \\ terminated) and hyperlinks.
+        assert_eq!(strip_controls("\x1b]0;cargo\x07a"), "a");
+        assert_eq!(
+            strip_controls("\x1b]8;;https://x.org\x1b\\link\x1b]8;;\x1b\\"),
+            "link"
+        );
+        // Character set selection and keypad mode.
+        assert_eq!(strip_controls("\x1b(B\x1b=\x1b[mtext"), "\x1b[mtext");
+        // Cursor save/restore and hiding.
+        assert_eq!(strip_controls("\x1b7\x1b[?25lx\x1b8\x1b[?25h"), "x");
+    }
+
+    #[test]
+    fn incomplete_tail_finds_a_cut_off_sequence() {
+        assert_eq!(incomplete_tail(b""), 0);
+        assert_eq!(incomplete_tail(b"plain"), 5);
+        assert_eq!(incomplete_tail(b"abc\x1b"), 3);
+        assert_eq!(incomplete_tail(b"abc\x1b["), 3);
+        assert_eq!(incomplete_tail(b"abc\x1b[1;3"), 3);
+        assert_eq!(incomplete_tail(b"abc\x1b("), 3);
+        assert_eq!(incomplete_tail(b"abc\x1b]0;title"), 3);
+        assert_eq!(incomplete_tail(b"abc\x1b[1m"), 7);
+        assert_eq!(incomplete_tail(b"\x1b[1mabc"), 7);
+        assert_eq!(incomplete_tail(b"\x1b]0;t\x07"), 6);
+        // Too long to be a real sequence: give up holding it back.
+        let mut malformed = b"abc\x1b[".to_vec();
+        malformed.extend_from_slice(&[b'1'; MAX_SEQUENCE + 1]);
+        assert_eq!(incomplete_tail(&malformed), malformed.len());
+    }
+}
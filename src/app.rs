@@ -2,26 +2,46 @@
 //
 // BitForge — main application state and egui render loop.
 
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use std::sync::mpsc::{Receiver, Sender};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use tokio::runtime::Runtime;
 
-use crate::compiler::{compile_bitcoin, compile_electrs};
+use crate::ansi;
+use crate::compiler::{compile_selection, plan_selection};
 use crate::deps::check_dependencies_task;
-use crate::env_setup::{brew_prefix, find_brew, macos_version, setup_build_environment};
+use crate::diagnose::{Diagnosis, Remedy};
+use crate::env_setup::{
+    brew_override, brew_prefix, find_brew, macos_version, setup_build_environment,
+};
 use crate::github::{fetch_bitcoin_versions, fetch_electrs_versions};
-use crate::messages::{log_msg, AppMessage, ConfirmRequest};
-
-/// Maximum log lines retained in memory.
-const MAX_LOG_LINES: usize = 4_000;
-/// Drop to this many lines when the cap is hit.
-const TRIM_TO_LINES: usize = MAX_LOG_LINES / 2;
+use crate::history::{self, format_bytes, format_duration, format_timestamp, BuildRecord, Outcome};
+use crate::log_buffer::LogBuffer;
+use crate::log_view::{
+    classify, FullLog, LogSearch, Row, Section, SectionRows, Sections, Severity, StageStatus,
+};
+use crate::messages::{log_msg, AppMessage, ConfirmItem, ConfirmRequest, Stream};
+use crate::options::{BuildOptions, BuildSelection, Target};
+use crate::process::{self, run_command, CommandSpec, Priority};
+use crate::retry;
+use crate::settings::{default_build_dir, Settings};
+
+/// Log lines retained in memory; the build's log file keeps everything.
+const MAX_LOG_LINES: usize = 200_000;
 /// Fixed pixel height for the build log terminal panel.
 const TERMINAL_HEIGHT: f32 = 260.0;
 /// Max width for the centred content column.
 const CONTENT_WIDTH: f32 = 860.0;
+/// Placeholder shown in the version comboboxes until GitHub answers.
+const LOADING: &str = "Loading...";
+/// Settings are written once they have been stable this long, so dragging
+/// the window edge does not rewrite the file every frame.
+const SETTINGS_SAVE_DELAY: Duration = Duration::from_millis(750);
+/// Height of the scrollable build history list.
+const HISTORY_HEIGHT: f32 = 220.0;
 
 // ─── Colour palette (macOS light mode) ───────────────────────────────────────
 
@@ -35,21 +55,22 @@ mod pal {
     pub const TEXT_PRIMARY: Color32 = Color32::from_rgb(20, 20, 25);
     pub const SUCCESS: Color32 = Color32::from_rgb(52, 199, 89); // macOS green
     pub const DANGER: Color32 = Color32::from_rgb(255, 59, 48); // macOS red
+    pub const CAUTION: Color32 = Color32::from_rgb(201, 117, 0); // macOS orange, darkened
     pub const PAGE_BG: Color32 = Color32::from_rgb(236, 236, 240); // window bg
     pub const STATUS_BG: Color32 = Color32::from_rgb(242, 242, 246);
 
     // Terminal stays dark
     pub const TERM_BG: Color32 = Color32::from_rgb(18, 18, 18);
     pub const TERM_TEXT: Color32 = Color32::from_rgb(0, 215, 0);
+    pub const TERM_HEADER: Color32 = Color32::from_rgb(225, 225, 230);
+    pub const TERM_ERROR: Color32 = Color32::from_rgb(255, 95, 87);
+    pub const TERM_WARNING: Color32 = Color32::from_rgb(255, 204, 0);
+    pub const TERM_STDERR: Color32 = Color32::from_rgb(235, 150, 120);
+    pub const TERM_MATCH: Color32 = Color32::from_rgb(70, 70, 20);
+    pub const TERM_MATCH_CURRENT: Color32 = Color32::from_rgb(130, 100, 0);
     pub const TERM_BORDER: Color32 = Color32::from_rgb(55, 55, 55);
 }
 
-// ─── Home directory ───────────────────────────────────────────────────────────
-
-fn home_dir() -> Option<PathBuf> {
-    std::env::var_os("HOME").map(PathBuf::from)
-}
-
 // ─── Modal ────────────────────────────────────────────────────────────────────
 
 enum Modal {
@@ -61,20 +82,29 @@ enum Modal {
     Confirm {
         title: String,
         message: String,
-        response_tx: tokio::sync::oneshot::Sender<bool>,
+        /// Checklist rows with their live tick state; empty for Yes/No.
+        items: Vec<ConfirmItem>,
+        response_tx: tokio::sync::oneshot::Sender<Option<Vec<bool>>>,
+    },
+    /// A failed build whose cause `diagnose` recognised.
+    Diagnosis {
+        message: String,
+        diagnosis: Diagnosis,
     },
 }
 
 enum ModalAction {
     Close,
     Confirm(bool),
+    Remedy(Remedy),
 }
 
 // ─── App state ────────────────────────────────────────────────────────────────
 
 pub struct BitForgeApp {
     // Configuration
-    target: String,
+    target: Target,
+    options: BuildOptions,
     cores: usize,
     max_cores: usize,
     build_dir: String,
@@ -85,10 +115,42 @@ pub struct BitForgeApp {
     electrs_versions: Vec<String>,
     selected_electrs: String,
 
+    // Homebrew formula → version, from the last dependency check
+    installed_packages: BTreeMap<String, String>,
+
+    // Persistence: last saved snapshot, and when it first diverged from it
+    settings: Settings,
+    settings_dirty_since: Option<Instant>,
+    window_size: Option<[f32; 2]>,
+    keep_log_colors: bool,
+    priority: Priority,
+
+    // Build history (oldest first) and the panel's filters
+    history: Vec<BuildRecord>,
+    history_filter: String,
+    history_outcome: Option<Outcome>,
+
     // UI state
-    log_buffer: String,
-    log_line_count: usize,
+    log: LogBuffer,
+    /// Full on-disk log of the running or most recent build.
+    log_file: Option<PathBuf>,
+    full_log: FullLog,
+    log_search: LogSearch,
+    /// Show the full log even without a search (after "Jump to First Error").
+    log_full_view: bool,
+    log_jump_to_error: bool,
+    /// Row to bring into view on the next frame.
+    log_scroll_to: Option<usize>,
+    last_build_failed: bool,
+    /// Current build's stages, for the collapsible log sections.
+    sections: Sections,
+    /// Rows of the sectioned live log.
+    log_rows: SectionRows,
     progress: f32,
+    /// The running command has printed nothing for this long.
+    stall: Option<(Arc<str>, Duration)>,
+    /// The running task's commands are suspended.
+    paused: bool,
     is_busy: bool,
     status_bar: String,
 
@@ -105,13 +167,18 @@ pub struct BitForgeApp {
     runtime: Arc<Runtime>,
 
     // Environment
+    /// Homebrew field: a brew binary or prefix, empty to search.
+    brew_path: String,
     brew: Option<String>,
     brew_pfx: Option<String>,
+    /// Why the requested Homebrew location could not be used.
+    brew_error: Option<String>,
+    macos: String,
 }
 
 impl BitForgeApp {
     pub fn new(
-        _cc: &eframe::CreationContext<'_>,
+        cc: &eframe::CreationContext<'_>,
         runtime: Arc<Runtime>,
         msg_rx: Receiver<AppMessage>,
         msg_tx: Sender<AppMessage>,
@@ -123,41 +190,70 @@ impl BitForgeApp {
             .unwrap_or(1);
         let default_cores = max_cores.saturating_sub(1).max(1);
 
-        let brew = find_brew();
-        let brew_pfx = brew.as_deref().map(brew_prefix);
         let macos = macos_version();
 
-        let status_bar = format!(
-            "macOS {}   ·   Homebrew: {}   ·   {} CPUs",
-            macos,
-            brew_pfx.as_deref().unwrap_or("not found"),
-            max_cores,
-        );
-
-        let default_build_dir = home_dir()
-            .map(|h| {
-                h.join("Downloads/bitcoin_builds")
-                    .to_string_lossy()
-                    .into_owned()
-            })
-            .unwrap_or_else(|| "/tmp/bitcoin_builds".to_owned());
+        // A broken settings file must never stop the app from starting.
+        let (settings, settings_error) = match Settings::load() {
+            Ok(s) => (s, None),
+            Err(e) => (Settings::default(), Some(format!("{e:#}"))),
+        };
+        let brew_path = settings.brew_path.clone().unwrap_or_default();
+        let (build_history, history_error) = match history::load() {
+            Ok(h) => (h, None),
+            Err(e) => (Vec::new(), Some(format!("{e:#}"))),
+        };
+        process::set_priority(settings.priority);
+        retry::set_attempts(settings.network_attempts.unwrap_or(retry::DEFAULT_ATTEMPTS));
+        if let Some([w, h]) = settings.window_size {
+            cc.egui_ctx
+                .send_viewport_cmd(egui::ViewportCommand::InnerSize(egui::vec2(w, h)));
+        }
 
         let mut app = Self {
-            target: "Bitcoin".to_owned(),
-            cores: default_cores,
+            target: settings.target,
+            options: settings.options.clone(),
+            cores: settings
+                .cores
+                .map_or(default_cores, |c| c.clamp(1, max_cores)),
             max_cores,
-            build_dir: default_build_dir,
-
-            bitcoin_versions: vec!["Loading...".to_owned()],
-            selected_bitcoin: "Loading...".to_owned(),
-            electrs_versions: vec!["Loading...".to_owned()],
-            selected_electrs: "Loading...".to_owned(),
-
-            log_buffer: String::new(),
-            log_line_count: 0,
+            build_dir: settings
+                .build_dir
+                .clone()
+                .filter(|d| !d.trim().is_empty())
+                .unwrap_or_else(default_build_dir),
+
+            bitcoin_versions: vec![LOADING.to_owned()],
+            selected_bitcoin: LOADING.to_owned(),
+            electrs_versions: vec![LOADING.to_owned()],
+            selected_electrs: LOADING.to_owned(),
+
+            installed_packages: BTreeMap::new(),
+
+            window_size: settings.window_size,
+            keep_log_colors: settings.keep_log_colors,
+            priority: settings.priority,
+            settings,
+            settings_dirty_since: None,
+
+            history: build_history,
+            history_filter: String::new(),
+            history_outcome: None,
+
+            log: LogBuffer::new(MAX_LOG_LINES),
+            log_file: None,
+            full_log: FullLog::default(),
+            log_search: LogSearch::default(),
+            log_full_view: false,
+            log_jump_to_error: false,
+            log_scroll_to: None,
+            last_build_failed: false,
+            sections: Sections::default(),
+            log_rows: SectionRows::default(),
             progress: 0.0,
+            stall: None,
+            paused: false,
             is_busy: false,
-            status_bar,
+            status_bar: String::new(),
 
             modal: None,
 
@@ -168,9 +264,13 @@ impl BitForgeApp {
 
             runtime,
 
-            brew,
-            brew_pfx,
+            brew_path,
+            brew: None,
+            brew_pfx: None,
+            brew_error: None,
+            macos,
         };
+        app.locate_brew();
 
         // Splash — borrow ends before first append_log call
         let sep = "=".repeat(60);
@@ -180,12 +280,21 @@ impl BitForgeApp {
         app.append_log(&format!(
             "{sep}\nBitForge — Bitcoin Core & Electrs Compiler\n{sep}\n"
         ));
-        app.append_log(&format!("System: macOS {macos}\n"));
+        app.append_log(&format!("System: macOS {}\n", app.macos));
         app.append_log(&format!("Homebrew: {brew_str}\n"));
+        if let Some(e) = app.brew_error.clone() {
+            app.append_log(&format!("⚠️  {e}\n"));
+        }
         app.append_log(&format!("CPU Cores: {cpus}\n"));
         app.append_log(&format!("{sep}\n\n"));
         app.append_log("👉 Click \"Check & Install Dependencies\" to begin.\n\n");
         app.append_log("📝 Bitcoin Core and Electrs are compiled from source via GitHub.\n\n");
+        if let Some(e) = settings_error {
+            app.append_log(&format!("⚠️  Ignoring saved settings: {e}\n\n"));
+        }
+        if let Some(e) = history_error {
+            app.append_log(&format!("⚠️  Ignoring build history: {e}\n\n"));
+        }
 
         app.spawn_refresh_all_versions();
         app
@@ -194,107 +303,226 @@ impl BitForgeApp {
     // ─── Log helpers ──────────────────────────────────────────────────────────
 
     fn append_log(&mut self, msg: &str) {
-        // Process character-by-character so that bare \r (carriage return)
-        // gets true terminal semantics: go back to the start of the current
-        // line and overwrite it.  This keeps cmake/make/git progress lines
-        // as a single updating line instead of hundreds of stacked copies.
-        for ch in msg.chars() {
-            match ch {
-                '\r' => {
-                    // Carriage return: truncate back to the last newline so
-                    // the next characters overwrite the current line.
-                    if let Some(pos) = self.log_buffer.rfind('\n') {
-                        self.log_buffer.truncate(pos + 1);
-                    } else {
-                        self.log_buffer.clear();
-                    }
-                    // Don't change log_line_count — we're still on the same line.
-                }
-                '\n' => {
-                    self.log_buffer.push('\n');
-                    self.log_line_count += 1;
-                }
-                c => {
-                    self.log_buffer.push(c);
-                }
-            }
-        }
-
-        if self.log_line_count > MAX_LOG_LINES {
-            let drop_count = self.log_line_count.saturating_sub(TRIM_TO_LINES);
-            let mut remaining = drop_count;
-            if let Some(split_pos) = self.log_buffer.char_indices().find_map(|(i, c)| {
-                if c == '\n' {
-                    if remaining == 0 {
-                        return Some(i);
-                    }
-                    remaining -= 1;
-                }
-                None
-            }) {
-                self.log_buffer = self.log_buffer[split_pos + 1..].to_owned();
-                self.log_line_count = TRIM_TO_LINES;
-            }
-        }
+        self.log.push(msg, None);
     }
 
     // ─── Message drain ────────────────────────────────────────────────────────
 
     fn drain_messages(&mut self) {
+        // A verbose build sends hundreds of small chunks per frame; output
+        // from the same source is joined and appended in one go.
+        let mut pending: Option<(Option<Arc<str>>, String)> = None;
         while let Ok(msg) = self.msg_rx.try_recv() {
-            match msg {
-                AppMessage::Log(s) => self.append_log(&s),
-                AppMessage::Progress(v) => self.progress = v.clamp(0.0, 1.0),
-                AppMessage::BitcoinVersionsLoaded(versions) => {
-                    if let Some(first) = versions.first() {
-                        self.selected_bitcoin = first.clone();
+            let (stderr_of, text) = match msg {
+                AppMessage::Log(text) => (None, text),
+                AppMessage::Output {
+                    command,
+                    stream,
+                    text,
+                } => ((stream == Stream::Stderr).then_some(command), text),
+                other => {
+                    if let Some((stderr_of, text)) = pending.take() {
+                        self.log.push(&text, stderr_of.as_ref());
                     }
-                    self.bitcoin_versions = versions;
+                    self.handle_message(other);
+                    continue;
                 }
-                AppMessage::ElectrsVersionsLoaded(versions) => {
-                    if let Some(first) = versions.first() {
-                        self.selected_electrs = first.clone();
+            };
+            match &mut pending {
+                Some((source, buffered)) if *source == stderr_of => buffered.push_str(&text),
+                _ => {
+                    if let Some((stderr_of, text)) = pending.replace((stderr_of, text)) {
+                        self.log.push(&text, stderr_of.as_ref());
                     }
-                    self.electrs_versions = versions;
-                }
-                AppMessage::ShowDialog {
-                    title,
-                    message,
-                    is_error,
-                } => {
-                    self.modal = Some(Modal::Alert {
-                        title,
-                        message,
-                        is_error,
-                    });
-                }
-                AppMessage::TaskDone => {
-                    self.is_busy = false;
-                    self.progress = 0.0;
                 }
             }
         }
+        if let Some((stderr_of, text)) = pending {
+            self.log.push(&text, stderr_of.as_ref());
+        }
 
         if self.modal.is_none() {
             if let Ok(req) = self.confirm_rx.try_recv() {
                 self.modal = Some(Modal::Confirm {
                     title: req.title,
                     message: req.message,
+                    items: req.items,
                     response_tx: req.response_tx,
                 });
             }
         }
     }
 
+    /// Apply one message.  Log output normally arrives via `drain_messages`,
+    /// which batches it.
+    fn handle_message(&mut self, msg: AppMessage) {
+        match msg {
+            AppMessage::Log(s) => self.append_log(&s),
+            AppMessage::Output {
+                command,
+                stream,
+                text,
+            } => {
+                let stderr_of = (stream == Stream::Stderr).then_some(command);
+                self.log.push(&text, stderr_of.as_ref());
+            }
+            AppMessage::Progress(v) => self.progress = v.clamp(0.0, 1.0),
+            AppMessage::Stalled {
+                command,
+                silent_for,
+            } => self.stall = silent_for.map(|d| (command, d)),
+            AppMessage::BitcoinVersionsLoaded(versions) => {
+                if let Some(v) = pick_version(
+                    &versions,
+                    &self.selected_bitcoin,
+                    self.settings.bitcoin_version.as_deref(),
+                ) {
+                    self.selected_bitcoin = v;
+                }
+                self.bitcoin_versions = versions;
+            }
+            AppMessage::ElectrsVersionsLoaded(versions) => {
+                if let Some(v) = pick_version(
+                    &versions,
+                    &self.selected_electrs,
+                    self.settings.electrs_version.as_deref(),
+                ) {
+                    self.selected_electrs = v;
+                }
+                self.electrs_versions = versions;
+            }
+            AppMessage::InstalledPackages(packages) => {
+                self.installed_packages = packages.into_iter().collect();
+            }
+            AppMessage::StageStarted(title) => {
+                self.sections.start(title, self.log.current_line());
+            }
+            AppMessage::BuildFinished { success } => self.sections.finish_running(success),
+            AppMessage::LogFile(path) => {
+                self.log_file = Some(path);
+                self.log_search.restart();
+            }
+            // Already logged; totals are kept with the build record.
+            AppMessage::Usage(_) => {}
+            AppMessage::BuildRecorded(record) => {
+                self.last_build_failed = record.outcome == Outcome::Failed;
+                self.history.push(*record);
+            }
+            AppMessage::ShowDialog {
+                title,
+                message,
+                is_error,
+            } => {
+                self.modal = Some(Modal::Alert {
+                    title,
+                    message,
+                    is_error,
+                });
+            }
+            AppMessage::BuildFailed { message, diagnosis } => {
+                self.modal = Some(match diagnosis {
+                    Some(diagnosis) => Modal::Diagnosis { message, diagnosis },
+                    None => Modal::Alert {
+                        title: "Compilation Failed".into(),
+                        message,
+                        is_error: true,
+                    },
+                });
+            }
+            AppMessage::TaskDone => {
+                self.is_busy = false;
+                self.progress = 0.0;
+                self.stall = None;
+                if self.paused {
+                    self.paused = false;
+                    process::set_paused(false);
+                }
+            }
+        }
+    }
+
+    // ─── Settings persistence ─────────────────────────────────────────────────
+
+    /// Snapshot of everything that is persisted between launches.
+    fn current_settings(&self) -> Settings {
+        // While versions are loading keep the saved choice rather than
+        // overwriting it with the placeholder.
+        let chosen = |selected: &str, saved: &Option<String>| {
+            if selected == LOADING {
+                saved.clone()
+            } else {
+                Some(selected.to_owned())
+            }
+        };
+        Settings {
+            target: self.target,
+            cores: Some(self.cores),
+            build_dir: Some(self.build_dir.clone()),
+            bitcoin_version: chosen(&self.selected_bitcoin, &self.settings.bitcoin_version),
+            electrs_version: chosen(&self.selected_electrs, &self.settings.electrs_version),
+            options: self.options.clone(),
+            window_size: self.window_size,
+            keep_log_colors: self.keep_log_colors,
+            priority: self.priority,
+            network_attempts: self.settings.network_attempts,
+            brew_path: Some(self.brew_path.trim().to_owned()).filter(|p| !p.is_empty()),
+        }
+    }
+
+    /// Find Homebrew from `BITFORGE_BREW` or the Homebrew field and show
+    /// where it is in the status bar.  A location that holds no brew leaves
+    /// Homebrew unset, with the reason in `brew_error`.
+    fn locate_brew(&mut self) {
+        let custom = brew_override(Some(&self.brew_path));
+        (self.brew, self.brew_error) = match find_brew(custom.as_deref()) {
+            Ok(brew) => (brew, None),
+            Err(e) => (None, Some(format!("{e:#}"))),
+        };
+        self.brew_pfx = match &self.brew {
+            Some(brew) => self.runtime.block_on(brew_prefix(brew)),
+            None => None,
+        };
+        self.status_bar = format!(
+            "macOS {}   ·   Homebrew: {}   ·   {} CPUs",
+            self.macos,
+            self.brew_pfx.as_deref().unwrap_or("not found"),
+            self.max_cores,
+        );
+    }
+
+    /// Save settings once they have changed and settled.
+    fn persist_settings(&mut self) {
+        let current = self.current_settings();
+        if current == self.settings {
+            self.settings_dirty_since = None;
+            return;
+        }
+        let since = *self.settings_dirty_since.get_or_insert_with(Instant::now);
+        if since.elapsed() < SETTINGS_SAVE_DELAY {
+            return;
+        }
+        if let Err(e) = current.save() {
+            self.append_log(&format!("⚠️  Could not save settings: {e:#}\n"));
+        }
+        // Adopt the snapshot even on failure so a read-only config dir
+        // produces one warning, not one per frame.
+        self.settings = current;
+        self.settings_dirty_since = None;
+    }
+
     // ─── Background task spawners ─────────────────────────────────────────────
 
     fn spawn_check_deps(&mut self) {
         let brew = match self.brew.clone() {
             Some(b) => b,
             None => {
+                let message = match &self.brew_error {
+                    Some(e) => format!("{e}.\nCorrect the Homebrew location in Step 1 or BITFORGE_BREW."),
+                    None => "Homebrew is required.\nInstall it from https://brew.sh then restart BitForge.".into(),
+                };
                 self.modal = Some(Modal::Alert {
-                    title:    "Homebrew Not Found".into(),
-                    message:  "Homebrew is required.\nInstall it from https://brew.sh then restart BitForge.".into(),
+                    title: "Homebrew Not Found".into(),
+                    message,
                     is_error: true,
                 });
                 return;
@@ -302,6 +530,7 @@ impl BitForgeApp {
         };
 
         let env = setup_build_environment(self.brew_pfx.as_deref());
+        let selection = self.selection();
         let tx = self.msg_tx.clone();
         let confirm_tx = self.confirm_tx.clone();
 
@@ -309,7 +538,7 @@ impl BitForgeApp {
         self.append_log("\n>>> Starting dependency check...\n");
 
         self.runtime.spawn(async move {
-            match check_dependencies_task(brew, env, tx.clone(), confirm_tx).await {
+            match check_dependencies_task(brew, env, selection, tx.clone(), confirm_tx).await {
                 Ok(_) => {}
                 Err(e) => {
                     tx.send(AppMessage::ShowDialog {
@@ -328,7 +557,7 @@ impl BitForgeApp {
         let tx = self.msg_tx.clone();
         self.runtime.spawn(async move {
             log_msg(&tx, "\n📡 Fetching Bitcoin versions from GitHub...\n");
-            match fetch_bitcoin_versions().await {
+            match fetch_bitcoin_versions(&tx).await {
                 Ok(versions) => {
                     log_msg(
                         &tx,
@@ -355,7 +584,7 @@ impl BitForgeApp {
         let tx = self.msg_tx.clone();
         self.runtime.spawn(async move {
             log_msg(&tx, "\n📡 Fetching Electrs versions from GitHub...\n");
-            match fetch_electrs_versions().await {
+            match fetch_electrs_versions(&tx).await {
                 Ok(versions) => {
                     log_msg(
                         &tx,
@@ -383,117 +612,162 @@ impl BitForgeApp {
         self.spawn_refresh_electrs_versions();
     }
 
-    fn spawn_compile(&mut self) {
-        let target = self.target.clone();
-        let cores = self.cores;
-        let build_dir = PathBuf::from(&self.build_dir);
-        let bitcoin_ver = self.selected_bitcoin.clone();
-        let electrs_ver = self.selected_electrs.clone();
+    /// Snapshot of the current target, versions and options.
+    fn selection(&self) -> BuildSelection {
+        BuildSelection {
+            target: self.target,
+            bitcoin_version: self.selected_bitcoin.clone(),
+            electrs_version: self.selected_electrs.clone(),
+            options: self.options.clone(),
+        }
+    }
+
+    /// Restore a past build's settings and start it again.
+    fn rebuild(&mut self, record: &BuildRecord) {
+        self.target = record.target;
+        if let Some(v) = &record.bitcoin_version {
+            self.selected_bitcoin = v.clone();
+        }
+        if let Some(v) = &record.electrs_version {
+            self.selected_electrs = v.clone();
+        }
+        self.options = record.options.clone();
+        self.cores = record.cores.clamp(1, self.max_cores);
+        self.build_dir = record.build_dir.to_string_lossy().into_owned();
+        self.append_log(&format!("\n>>> Rebuilding {}\n", record.summary()));
+        self.spawn_compile();
+    }
 
-        let loading = |s: &str| s.is_empty() || s == "Loading...";
-        if (target == "Bitcoin" || target == "Both") && loading(&bitcoin_ver) {
+    /// The selection to build, or `None` — after alerting — while a version
+    /// it needs is still loading.
+    fn ready_selection(&mut self) -> Option<BuildSelection> {
+        let target = self.target;
+        let loading = |s: &str| s.is_empty() || s == LOADING;
+        if target.includes_bitcoin() && loading(&self.selected_bitcoin) {
             self.modal = Some(Modal::Alert {
                 title: "Not Ready".into(),
                 message: "Please wait for Bitcoin versions to load, or click Refresh.".into(),
                 is_error: true,
             });
-            return;
+            return None;
         }
-        if (target == "Electrs" || target == "Both") && loading(&electrs_ver) {
+        if target.includes_electrs() && loading(&self.selected_electrs) {
             self.modal = Some(Modal::Alert {
                 title: "Not Ready".into(),
                 message: "Please wait for Electrs versions to load, or click Refresh.".into(),
                 is_error: true,
             });
-            return;
+            return None;
         }
+        Some(self.selection())
+    }
 
+    fn spawn_compile(&mut self) {
+        let Some(selection) = self.ready_selection() else {
+            return;
+        };
+        let cores = self.cores;
+        let build_dir = PathBuf::from(&self.build_dir);
         let env = setup_build_environment(self.brew_pfx.as_deref());
+        let log_colors = self.keep_log_colors;
         let tx = self.msg_tx.clone();
 
         self.is_busy = true;
         self.progress = 0.0;
+        self.last_build_failed = false;
+        self.log_full_view = false;
+        self.sections.clear();
 
         self.runtime.spawn(async move {
-            tx.send(AppMessage::Progress(0.05)).ok();
-            let mut output_dirs: Vec<String> = Vec::new();
-            let mut error_occurred = false;
-
-            if target == "Bitcoin" || target == "Both" {
-                tx.send(AppMessage::Progress(0.1)).ok();
-                match compile_bitcoin(&bitcoin_ver, &build_dir, cores, &env, &tx).await {
-                    Ok(dir) => {
-                        output_dirs.push(dir.to_string_lossy().into_owned());
-                        tx.send(AppMessage::Progress(if target == "Both" {
-                            0.5
-                        } else {
-                            0.95
-                        }))
-                        .ok();
-                    }
-                    Err(e) => {
-                        log_msg(&tx, &format!("\n❌ Compilation failed: {e}\n"));
-                        tx.send(AppMessage::ShowDialog {
-                            title: "Compilation Failed".into(),
-                            message: e.to_string(),
-                            is_error: true,
-                        })
-                        .ok();
-                        error_occurred = true;
-                    }
-                }
-            }
+            // Failures are logged and shown as a dialog by compile_selection.
+            let _ = compile_selection(&selection, &build_dir, cores, &env, log_colors, &tx).await;
+            tx.send(AppMessage::TaskDone).ok();
+        });
+    }
 
-            if !error_occurred && (target == "Electrs" || target == "Both") {
-                tx.send(AppMessage::Progress(if target == "Both" {
-                    0.55
-                } else {
-                    0.1
-                }))
-                .ok();
-                match compile_electrs(&electrs_ver, &build_dir, cores, &env, &tx).await {
-                    Ok(dir) => {
-                        output_dirs.push(dir.to_string_lossy().into_owned());
-                        tx.send(AppMessage::Progress(1.0)).ok();
-                    }
-                    Err(e) => {
-                        log_msg(&tx, &format!("\n❌ Compilation failed: {e}\n"));
-                        tx.send(AppMessage::ShowDialog {
-                            title: "Compilation Failed".into(),
-                            message: e.to_string(),
-                            is_error: true,
-                        })
-                        .ok();
-                        error_occurred = true;
-                    }
-                }
+    /// Log what building the current selection would do, without building.
+    fn spawn_dry_run(&mut self) {
+        let Some(selection) = self.ready_selection() else {
+            return;
+        };
+        let cores = self.cores;
+        let build_dir = PathBuf::from(&self.build_dir);
+        let env = setup_build_environment(self.brew_pfx.as_deref());
+        let tx = self.msg_tx.clone();
+
+        self.is_busy = true;
+        self.log_full_view = false;
+
+        self.runtime.spawn(async move {
+            match plan_selection(&selection, &build_dir, cores, &env).await {
+                Ok(plan) => log_msg(&tx, &format!("\n{plan}\n")),
+                Err(e) => log_msg(&tx, &format!("\n❌ Dry run failed: {e:#}\n")),
             }
+            tx.send(AppMessage::TaskDone).ok();
+        });
+    }
 
-            if !error_occurred {
-                tx.send(AppMessage::Progress(1.0)).ok();
-                let dirs_list = output_dirs
-                    .iter()
-                    .map(|d| format!("• {d}"))
-                    .collect::<Vec<_>>()
-                    .join("\n");
-                tx.send(AppMessage::ShowDialog {
-                    title: "Compilation Complete".into(),
-                    message: format!(
-                        "✅ {target} compiled successfully!\n\nBinaries saved to:\n{dirs_list}"
-                    ),
-                    is_error: false,
-                })
-                .ok();
+    /// Carry out a diagnosis's remedy: run the fix, or build again with
+    /// fewer jobs.
+    fn apply_remedy(&mut self, remedy: Remedy) {
+        let env = setup_build_environment(self.brew_pfx.as_deref());
+        let cmd = match remedy {
+            Remedy::FewerJobs => {
+                self.cores = (self.cores / 2).max(1);
+                self.append_log(&format!("\n🩺 Retrying with {} jobs.\n", self.cores));
+                self.spawn_compile();
+                return;
             }
+            Remedy::Brew(args) => match &self.brew {
+                Some(brew) => CommandSpec::new(brew, &env).args(args),
+                None => {
+                    self.modal = Some(Modal::Alert {
+                        title: "Homebrew Not Found".into(),
+                        message: "Install Homebrew from https://brew.sh then restart BitForge."
+                            .into(),
+                        is_error: true,
+                    });
+                    return;
+                }
+            },
+            Remedy::Run(program, args) => CommandSpec::new(program, &env).args(args),
+        };
+        let tx = self.msg_tx.clone();
 
+        self.is_busy = true;
+        self.log_full_view = false;
+
+        self.runtime.spawn(async move {
+            match run_command(&cmd, &tx).await {
+                Ok(()) => log_msg(&tx, "\n✓ Done — start the compilation again.\n"),
+                Err(e) => {
+                    tx.send(AppMessage::ShowDialog {
+                        title: "Fix Failed".into(),
+                        message: format!("{e:#}"),
+                        is_error: true,
+                    })
+                    .ok();
+                }
+            }
             tx.send(AppMessage::TaskDone).ok();
         });
     }
 
+    /// Suspend the running task's commands, or continue them.
+    fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        process::set_paused(self.paused);
+        self.append_log(if self.paused {
+            "\n⏸  Paused.\n"
+        } else {
+            "▶  Resumed.\n"
+        });
+    }
+
     // ─── Modal rendering ──────────────────────────────────────────────────────
 
     fn render_modal(&mut self, ctx: &egui::Context) {
-        let action: Option<ModalAction> = match &self.modal {
+        let action: Option<ModalAction> = match &mut self.modal {
             None => return,
 
             Some(Modal::Alert {
@@ -540,9 +814,16 @@ impl BitForgeApp {
                 }
             }
 
-            Some(Modal::Confirm { title, message, .. }) => {
+            Some(Modal::Confirm {
+                title,
+                message,
+                items,
+                ..
+            }) => {
                 let title_str = title.clone();
                 let msg_str = message.clone();
+                let is_checklist = !items.is_empty();
+                let any_ticked = items.iter().any(|item| item.checked);
                 let mut answer: Option<bool> = None;
 
                 egui::Window::new(title_str.as_str())
@@ -554,11 +835,42 @@ impl BitForgeApp {
                     .show(ctx, |ui| {
                         ui.add_space(6.0);
                         ui.label(msg_str.as_str());
+                        if is_checklist {
+                            ui.add_space(8.0);
+                            egui::ScrollArea::vertical()
+                                .id_source("confirm_checklist")
+                                .max_height(260.0)
+                                .show(ui, |ui| {
+                                    for item in items.iter_mut() {
+                                        ui.horizontal(|ui| {
+                                            ui.checkbox(
+                                                &mut item.checked,
+                                                egui::RichText::new(&item.label)
+                                                    .monospace()
+                                                    .strong(),
+                                            );
+                                            ui.label(
+                                                egui::RichText::new(&item.detail)
+                                                    .size(12.0)
+                                                    .color(pal::LABEL_MUTED),
+                                            );
+                                        });
+                                    }
+                                });
+                        }
                         ui.add_space(12.0);
                         ui.separator();
                         ui.add_space(6.0);
                         ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
-                            if ui.add(accent_button("Install")).clicked() {
+                            let label = if is_checklist {
+                                "Install Selected"
+                            } else {
+                                "Install"
+                            };
+                            if ui
+                                .add_enabled(!is_checklist || any_ticked, accent_button(label))
+                                .clicked()
+                            {
                                 answer = Some(true);
                             }
                             ui.add_space(6.0);
@@ -574,6 +886,62 @@ impl BitForgeApp {
 
                 answer.map(ModalAction::Confirm)
             }
+
+            Some(Modal::Diagnosis { message, diagnosis }) => {
+                let mut action = None;
+
+                egui::Window::new("Compilation Failed")
+                    .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                    .collapsible(false)
+                    .resizable(false)
+                    .min_width(380.0)
+                    .max_width(500.0)
+                    .show(ctx, |ui| {
+                        ui.add_space(2.0);
+                        ui.colored_label(
+                            pal::DANGER,
+                            egui::RichText::new("⛔  Error").strong().size(14.0),
+                        );
+                        ui.add_space(4.0);
+                        ui.separator();
+                        ui.add_space(6.0);
+                        ui.label(message.as_str());
+                        ui.add_space(10.0);
+                        ui.label(
+                            egui::RichText::new(format!("🩺  {}", diagnosis.title))
+                                .strong()
+                                .size(14.0),
+                        );
+                        ui.add_space(4.0);
+                        ui.label(diagnosis.explanation);
+                        ui.add_space(4.0);
+                        ui.label(egui::RichText::new(diagnosis.fix).strong());
+                        ui.add_space(4.0);
+                        ui.label(
+                            egui::RichText::new(&diagnosis.evidence)
+                                .monospace()
+                                .size(11.0)
+                                .color(pal::LABEL_MUTED),
+                        );
+                        ui.add_space(12.0);
+                        ui.separator();
+                        ui.add_space(6.0);
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
+                            if let Some(remedy) = diagnosis.remedy {
+                                if ui.add(accent_button(&remedy.label())).clicked() {
+                                    action = Some(ModalAction::Remedy(remedy));
+                                }
+                                ui.add_space(6.0);
+                            }
+                            if ui.button(egui::RichText::new("Close").size(13.0)).clicked() {
+                                action = Some(ModalAction::Close);
+                            }
+                        });
+                        ui.add_space(2.0);
+                    });
+
+                action
+            }
         };
 
         match action {
@@ -582,10 +950,354 @@ impl BitForgeApp {
                 self.modal = None;
             }
             Some(ModalAction::Confirm(answer)) => {
-                if let Some(Modal::Confirm { response_tx, .. }) = self.modal.take() {
-                    response_tx.send(answer).ok();
+                if let Some(Modal::Confirm {
+                    items, response_tx, ..
+                }) = self.modal.take()
+                {
+                    let reply = answer.then(|| items.iter().map(|item| item.checked).collect());
+                    response_tx.send(reply).ok();
+                }
+            }
+            Some(ModalAction::Remedy(remedy)) => {
+                self.modal = None;
+                self.apply_remedy(remedy);
+            }
+        }
+    }
+
+    // ─── Log files ────────────────────────────────────────────────────────────
+
+    fn open_or_alert(&mut self, path: &Path) {
+        if let Err(e) = open_path(path) {
+            self.modal = Some(Modal::Alert {
+                title: "Could Not Open".into(),
+                message: format!("{}\n{e}", path.display()),
+                is_error: true,
+            });
+        }
+    }
+
+    /// Save the latest build's full log, or the on-screen log if no build
+    /// has written one yet.
+    fn save_log_as(&mut self) {
+        let source = self.log_file.clone().filter(|p| p.exists());
+        let name = source.as_ref().and_then(|p| p.file_name()).map_or_else(
+            || "bitforge.log".to_owned(),
+            |n| n.to_string_lossy().into_owned(),
+        );
+        let Some(dest) = rfd::FileDialog::new().set_file_name(name).save_file() else {
+            return;
+        };
+        let result = match &source {
+            Some(src) => std::fs::copy(src, &dest).map(|_| ()),
+            None => {
+                let text = self.log.to_text();
+                if self.keep_log_colors {
+                    std::fs::write(&dest, &text)
+                } else {
+                    std::fs::write(&dest, ansi::strip(&text).as_bytes())
                 }
             }
+        };
+        if let Err(e) = result {
+            self.modal = Some(Modal::Alert {
+                title: "Could Not Save Log".into(),
+                message: format!("{}\n{e}", dest.display()),
+                is_error: true,
+            });
+        }
+    }
+
+    // ─── Log search / filter ──────────────────────────────────────────────────
+
+    /// Whether the panel shows the full log file rather than the live tail.
+    fn showing_full_log(&self) -> bool {
+        self.log_search.is_active() || self.log_full_view
+    }
+
+    fn render_log_toolbar(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let search = ui.add(
+                egui::TextEdit::singleline(&mut self.log_search.query)
+                    .desired_width(220.0)
+                    .hint_text("Search full log"),
+            );
+            // Enter in the search box moves to the next match.
+            if search.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                self.log_scroll_to = self.log_search.step(!ui.input(|i| i.modifiers.shift));
+                search.request_focus();
+            }
+            let has_matches = self.log_search.match_count() > 0;
+            if ui
+                .add_enabled(has_matches, egui::Button::new("▲"))
+                .clicked()
+            {
+                self.log_scroll_to = self.log_search.step(false);
+            }
+            if ui
+                .add_enabled(has_matches, egui::Button::new("▼"))
+                .clicked()
+            {
+                self.log_scroll_to = self.log_search.step(true);
+            }
+            if !self.log_search.query.is_empty() {
+                let count = self.log_search.match_count();
+                let text = if count == 0 {
+                    "No matches".to_owned()
+                } else {
+                    format!("{} of {count}", self.log_search.current + 1)
+                };
+                ui.label(egui::RichText::new(text).size(12.0).color(pal::LABEL_MUTED));
+            }
+            ui.checkbox(&mut self.log_search.problems_only, "Errors & warnings only");
+            ui.checkbox(&mut self.keep_log_colors, "Keep colours in log files")
+                .on_hover_text("Write ANSI colour codes to saved logs instead of plain text");
+
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if self.showing_full_log()
+                    && ui
+                        .button("Live")
+                        .on_hover_text("Clear the search and follow the build")
+                        .clicked()
+                {
+                    self.log_search.query.clear();
+                    self.log_search.problems_only = false;
+                    self.log_full_view = false;
+                }
+                if self.last_build_failed && ui.button("Jump to First Error").clicked() {
+                    self.log_full_view = true;
+                    self.log_jump_to_error = true;
+                }
+            });
+        });
+    }
+
+    /// Draw only the visible log rows in the tools' own colours, falling
+    /// back to severity colours.  Search, the filter and jumps work on the
+    /// full log file when there is one.
+    fn render_log_lines(&mut self, ui: &mut egui::Ui) {
+        let font = egui::FontId::monospace(11.5);
+        let row_height = ui.fonts(|f| f.row_height(&font));
+
+        if !self.showing_full_log() {
+            self.log_rows
+                .update(&self.sections, self.log.first_line(), self.log.line_count());
+            let (log, rows) = (&self.log, self.log_rows.rows());
+            let mut toggled = None;
+            egui::ScrollArea::both()
+                .id_source("build_log")
+                .stick_to_bottom(true)
+                .max_height(TERMINAL_HEIGHT)
+                .auto_shrink([false, false])
+                .show_rows(ui, row_height, rows.len(), |ui, range| {
+                    for row in rows.range(range) {
+                        match *row {
+                            Row::Line(line) => {
+                                let i = line - log.first_line();
+                                let stderr = log.stderr_of(i);
+                                let text = styled_line(log.line(i), &font, stderr.is_some(), None);
+                                let label = ui.add(egui::Label::new(text).extend());
+                                if let Some(command) = stderr {
+                                    label.on_hover_text(format!("stderr of: {command}"));
+                                }
+                            }
+                            Row::Header(i) => {
+                                let text = section_header(&self.sections.sections()[i], &font);
+                                let header =
+                                    egui::Label::new(text).extend().sense(egui::Sense::click());
+                                if ui
+                                    .add(header)
+                                    .on_hover_cursor(egui::CursorIcon::PointingHand)
+                                    .clicked()
+                                {
+                                    toggled = Some(i);
+                                }
+                            }
+                        }
+                    }
+                });
+            if let Some(i) = toggled {
+                self.sections.toggle(i);
+            }
+            return;
+        }
+
+        match &self.log_file {
+            Some(path) => self.full_log.refresh(path),
+            None => self.log.make_contiguous(),
+        }
+        let (full_log, log) = (&self.full_log, &self.log);
+        let (lines, first_line) = if self.log_file.is_some() {
+            (full_log.lines(), 0)
+        } else {
+            (log.as_slice(), log.first_line())
+        };
+        let is_stderr = |i: usize| {
+            if self.log_file.is_some() {
+                full_log.is_stderr(i)
+            } else {
+                log.stderr_of(i).is_some()
+            }
+        };
+        self.log_search.refresh(lines, first_line);
+        if std::mem::take(&mut self.log_jump_to_error) {
+            self.log_scroll_to = self.log_search.first_error_row(lines, is_stderr);
+        }
+
+        let mut area = egui::ScrollArea::both()
+            .id_source("build_log_full")
+            .max_height(TERMINAL_HEIGHT)
+            .auto_shrink([false, false]);
+        if let Some(row) = self.log_scroll_to.take() {
+            let offset = row as f32 * (row_height + ui.spacing().item_spacing.y);
+            area = area.vertical_scroll_offset((offset - TERMINAL_HEIGHT / 2.0).max(0.0));
+        }
+        let search = &self.log_search;
+        let current = search.current_row();
+        area.show_rows(ui, row_height, search.rows().len(), |ui, range| {
+            for row in range {
+                let highlight = if current == Some(row) {
+                    Some(pal::TERM_MATCH_CURRENT)
+                } else if search.is_match(row) {
+                    Some(pal::TERM_MATCH)
+                } else {
+                    None
+                };
+                let i = search.rows()[row];
+                let text = styled_line(&lines[i], &font, is_stderr(i), highlight);
+                ui.add(egui::Label::new(text).extend());
+            }
+        });
+    }
+
+    // ─── Build history panel ──────────────────────────────────────────────────
+
+    fn render_history(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.history_filter)
+                    .desired_width(260.0)
+                    .hint_text("Filter by version, commit or option"),
+            );
+            egui::ComboBox::from_id_source("history_outcome")
+                .selected_text(self.history_outcome.map_or("All", Outcome::label))
+                .width(110.0)
+                .show_ui(ui, |ui: &mut egui::Ui| {
+                    ui.selectable_value(&mut self.history_outcome, None, "All");
+                    for outcome in [Outcome::Succeeded, Outcome::Failed] {
+                        ui.selectable_value(
+                            &mut self.history_outcome,
+                            Some(outcome),
+                            outcome.label(),
+                        );
+                    }
+                });
+        });
+        ui.add_space(6.0);
+
+        let needle = self.history_filter.trim().to_lowercase();
+        let visible: Vec<&BuildRecord> = self
+            .history
+            .iter()
+            .rev()
+            .filter(|r| self.history_outcome.is_none() || self.history_outcome == Some(r.outcome))
+            .filter(|r| {
+                needle.is_empty()
+                    || [
+                        r.summary(),
+                        r.options_summary(),
+                        r.error.clone().unwrap_or_default(),
+                    ]
+                    .iter()
+                    .any(|field| field.to_lowercase().contains(&needle))
+            })
+            .collect();
+
+        if visible.is_empty() {
+            let text = if self.history.is_empty() {
+                "No builds yet."
+            } else {
+                "No builds match the filter."
+            };
+            ui.label(egui::RichText::new(text).size(12.0).color(pal::LABEL_MUTED));
+            return;
+        }
+
+        let mut rebuild: Option<BuildRecord> = None;
+        let mut open: Option<PathBuf> = None;
+
+        egui::ScrollArea::vertical()
+            .id_source("build_history")
+            .max_height(HISTORY_HEIGHT)
+            .auto_shrink([false, true])
+            .show(ui, |ui| {
+                for (i, record) in visible.iter().enumerate() {
+                    if i > 0 {
+                        ui.separator();
+                    }
+                    ui.horizontal(|ui| {
+                        let (icon, color) = match record.outcome {
+                            Outcome::Succeeded => ("✅", pal::SUCCESS),
+                            Outcome::Failed => ("⛔", pal::DANGER),
+                        };
+                        ui.colored_label(color, icon);
+                        ui.label(egui::RichText::new(record.summary()).strong().size(12.5));
+
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            if ui
+                                .add_enabled(!self.is_busy, egui::Button::new("Rebuild"))
+                                .on_hover_text("Build again with the same settings")
+                                .clicked()
+                            {
+                                rebuild = Some((*record).clone());
+                            }
+                            let log = record.log_file.as_ref().filter(|p| p.exists());
+                            if ui
+                                .add_enabled(log.is_some(), egui::Button::new("Open Log"))
+                                .clicked()
+                            {
+                                open = log.cloned();
+                            }
+                            let folder = record.output_dirs.first().unwrap_or(&record.build_dir);
+                            if ui
+                                .add_enabled(folder.exists(), egui::Button::new("Open Folder"))
+                                .clicked()
+                            {
+                                open = Some(folder.clone());
+                            }
+                        });
+                    });
+
+                    let mut details = vec![
+                        format_timestamp(record.started_at),
+                        format_duration(record.duration_secs()),
+                        format!("{} cores", record.cores),
+                    ];
+                    if let Some(usage) = &record.usage {
+                        let cpu_secs = (usage.user_ms + usage.sys_ms) / 1_000;
+                        details.push(format!("{} CPU", format_duration(cpu_secs)));
+                        details.push(format!("peak {}", format_bytes(usage.peak_rss_bytes)));
+                    }
+                    let options = record.options_summary();
+                    if !options.is_empty() {
+                        details.push(options);
+                    }
+                    ui.label(
+                        egui::RichText::new(details.join("   ·   "))
+                            .size(11.5)
+                            .color(pal::LABEL_MUTED),
+                    );
+                    if let Some(error) = &record.error {
+                        ui.label(egui::RichText::new(error).size(11.5).color(pal::DANGER));
+                    }
+                }
+            });
+
+        if let Some(path) = open {
+            self.open_or_alert(&path);
+        }
+        if let Some(record) = rebuild {
+            self.rebuild(&record);
         }
     }
 
@@ -615,7 +1327,7 @@ impl BitForgeApp {
             ui.horizontal(|ui| {
                 ui.label(
                     egui::RichText::new(
-                        "Scans for required Homebrew packages and the Rust toolchain.",
+                        "Scans for the Homebrew packages and Rust toolchain the selected build needs.",
                     )
                     .size(12.5)
                     .color(pal::LABEL_MUTED),
@@ -629,6 +1341,50 @@ impl BitForgeApp {
                     }
                 });
             });
+
+            ui.add_space(4.0);
+            ui.horizontal(|ui| {
+                ui.label(egui::RichText::new("Homebrew").color(pal::LABEL_MUTED));
+                let field = ui
+                    .add(
+                        egui::TextEdit::singleline(&mut self.brew_path)
+                            .desired_width(320.0)
+                            .hint_text("auto")
+                            .font(egui::TextStyle::Monospace),
+                    )
+                    .on_hover_text(
+                        "brew binary or Homebrew prefix, e.g. /home/linuxbrew/.linuxbrew.\n\
+                         Leave empty to search the usual places.  BITFORGE_BREW takes precedence.",
+                    );
+                if field.lost_focus() {
+                    self.locate_brew();
+                    if let Some(e) = self.brew_error.clone() {
+                        self.append_log(&format!("⚠️  {e}\n"));
+                    }
+                }
+            });
+
+            if !self.installed_packages.is_empty() {
+                ui.add_space(4.0);
+                egui::CollapsingHeader::new(
+                    egui::RichText::new(format!(
+                        "Installed packages ({})",
+                        self.installed_packages.len()
+                    ))
+                    .size(12.0)
+                    .color(pal::LABEL_MUTED),
+                )
+                .id_source("installed_packages")
+                .show(ui, |ui| {
+                    for (name, version) in &self.installed_packages {
+                        ui.label(
+                            egui::RichText::new(format!("{name} {version}"))
+                                .monospace()
+                                .size(11.5),
+                        );
+                    }
+                });
+            }
         });
 
         ui.add_space(10.0);
@@ -642,11 +1398,11 @@ impl BitForgeApp {
                     // Row 1: Target + Cores
                     ui.label(egui::RichText::new("Target").color(pal::LABEL_MUTED));
                     egui::ComboBox::from_id_source("target_combo")
-                        .selected_text(&self.target)
+                        .selected_text(self.target.label())
                         .width(140.0)
                         .show_ui(ui, |ui: &mut egui::Ui| {
-                            for opt in &["Bitcoin", "Electrs", "Both"] {
-                                ui.selectable_value(&mut self.target, opt.to_string(), *opt);
+                            for opt in Target::ALL {
+                                ui.selectable_value(&mut self.target, opt, opt.label());
                             }
                         });
 
@@ -679,6 +1435,55 @@ impl BitForgeApp {
                         }
                     }
                     ui.end_row();
+
+                    // Row 3: Bitcoin Core build options
+                    ui.label(egui::RichText::new("Options").color(pal::LABEL_MUTED));
+                    ui.add_enabled_ui(self.target.includes_bitcoin(), |ui| {
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut self.options.wallet, "Wallet")
+                                .on_hover_text("Build the descriptor wallet (needs SQLite)");
+                            ui.checkbox(&mut self.options.zmq, "ZMQ")
+                                .on_hover_text("Enable ZMQ notifications (needs ZeroMQ)");
+                        });
+                    });
+                    ui.end_row();
+
+                    // Row 4: Electrs Rust toolchain
+                    ui.label(egui::RichText::new("Rust Toolchain").color(pal::LABEL_MUTED));
+                    ui.add_enabled(
+                        self.target.includes_electrs(),
+                        egui::TextEdit::singleline(&mut self.options.rust_toolchain)
+                            .desired_width(140.0)
+                            .hint_text("auto")
+                            .font(egui::TextStyle::Monospace),
+                    )
+                    .on_hover_text(
+                        "rustup toolchain for Electrs, e.g. 1.80.0 or stable.\n\
+                         Leave empty to use the checkout's rust-toolchain file or rustup's default.",
+                    );
+                    ui.end_row();
+
+                    // Row 5: CPU / I/O priority
+                    ui.label(egui::RichText::new("Priority").color(pal::LABEL_MUTED));
+                    let before = self.priority;
+                    egui::ComboBox::from_id_source("priority_combo")
+                        .selected_text(self.priority.label())
+                        .width(140.0)
+                        .show_ui(ui, |ui: &mut egui::Ui| {
+                            for opt in Priority::ALL {
+                                ui.selectable_value(&mut self.priority, opt, opt.label());
+                            }
+                        })
+                        .response
+                        .on_hover_text(
+                            "Background: build at reduced CPU and I/O priority.\n\
+                             Adaptive: also use fewer jobs while the machine is busy.\n\
+                             Applies from the next command.",
+                        );
+                    if self.priority != before {
+                        process::set_priority(self.priority);
+                    }
+                    ui.end_row();
                 });
         });
 
@@ -734,7 +1539,9 @@ impl BitForgeApp {
 
         // ── Progress ──────────────────────────────────────────────────────────
         section_card(ui, "Build Progress", |ui| {
-            let label = if self.is_busy {
+            let label = if self.paused {
+                "Paused".to_owned()
+            } else if self.is_busy {
                 format!("{:.0}%", self.progress * 100.0)
             } else if self.progress >= 1.0 {
                 "Complete".to_owned()
@@ -742,26 +1549,75 @@ impl BitForgeApp {
                 "Idle".to_owned()
             };
 
+            let mut toggle_pause = false;
             ui.horizontal(|ui| {
+                let controls = if self.is_busy { 150.0 } else { 56.0 };
                 ui.add(
                     egui::ProgressBar::new(self.progress)
-                        .desired_width(ui.available_width() - 56.0)
-                        .animate(self.is_busy)
+                        .desired_width(ui.available_width() - controls)
+                        .animate(self.is_busy && !self.paused)
                         .text(""),
                 );
                 ui.add_space(6.0);
                 ui.label(egui::RichText::new(label).small().color(pal::LABEL_MUTED));
+                if self.is_busy {
+                    let button = if self.paused {
+                        "▶  Resume"
+                    } else {
+                        "⏸  Pause"
+                    };
+                    toggle_pause = ui
+                        .button(button)
+                        .on_hover_text("Suspend or continue the running command")
+                        .clicked();
+                }
             });
+            if toggle_pause {
+                self.toggle_pause();
+            }
+            if self.paused {
+                ui.label(
+                    egui::RichText::new("⏸ Paused — the running command is suspended")
+                        .small()
+                        .color(pal::CAUTION),
+                );
+            } else if let Some((command, silent_for)) = &self.stall {
+                let text = format!(
+                    "⏳ No output for {} — {command}",
+                    format_duration(silent_for.as_secs())
+                );
+                ui.add(
+                    egui::Label::new(egui::RichText::new(text).small().color(pal::CAUTION))
+                        .truncate(),
+                );
+            }
         });
 
         ui.add_space(10.0);
 
         // ── Build log terminal — FIXED HEIGHT, never resizes ──────────────────
-        ui.label(
-            egui::RichText::new("Build Log")
-                .strong()
-                .color(pal::TEXT_PRIMARY),
-        );
+        ui.horizontal(|ui| {
+            ui.label(
+                egui::RichText::new("Build Log")
+                    .strong()
+                    .color(pal::TEXT_PRIMARY),
+            );
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui.button("Save Log As…").clicked() {
+                    self.save_log_as();
+                }
+                let full_log = self.log_file.clone().filter(|p| p.exists());
+                let clicked = ui
+                    .add_enabled(full_log.is_some(), egui::Button::new("Open Full Log"))
+                    .on_hover_text("The complete, untrimmed log of the latest build")
+                    .clicked();
+                if let (true, Some(path)) = (clicked, full_log) {
+                    self.open_or_alert(&path);
+                }
+            });
+        });
+        ui.add_space(4.0);
+        self.render_log_toolbar(ui);
         ui.add_space(4.0);
 
         egui::Frame {
@@ -777,22 +1633,7 @@ impl BitForgeApp {
             // allocates more or less space as log content grows.
             ui.set_min_height(TERMINAL_HEIGHT);
             ui.set_max_height(TERMINAL_HEIGHT);
-
-            egui::ScrollArea::vertical()
-                .id_source("build_log")
-                .stick_to_bottom(true)
-                .max_height(TERMINAL_HEIGHT)
-                .min_scrolled_height(TERMINAL_HEIGHT)
-                .auto_shrink([false, false])
-                .show(ui, |ui| {
-                    ui.set_width(ui.available_width());
-                    ui.label(
-                        egui::RichText::new(&self.log_buffer)
-                            .color(pal::TERM_TEXT)
-                            .monospace()
-                            .size(11.5),
-                    );
-                });
+            self.render_log_lines(ui);
         });
 
         ui.add_space(18.0);
@@ -821,12 +1662,112 @@ impl BitForgeApp {
             {
                 self.spawn_compile();
             }
+            ui.add_space(6.0);
+            if ui
+                .add_enabled(!self.is_busy, egui::Button::new("🔍  Dry Run"))
+                .on_hover_text(
+                    "Show the directories, environment and every command the build \
+                     would run, without running anything",
+                )
+                .clicked()
+            {
+                self.spawn_dry_run();
+            }
         });
+
+        ui.add_space(18.0);
+
+        // ── Build history ─────────────────────────────────────────────────────
+        section_card(ui, "Build History", |ui| self.render_history(ui));
     }
 }
 
 // ─── UI helpers ───────────────────────────────────────────────────────────────
 
+/// Choose which version to select after a (re)load: keep the current
+/// choice if GitHub still lists it, else the saved one, else the newest.
+fn pick_version(versions: &[String], current: &str, saved: Option<&str>) -> Option<String> {
+    [Some(current), saved]
+        .into_iter()
+        .flatten()
+        .find(|v| versions.iter().any(|listed| listed == v))
+        .map(str::to_owned)
+        .or_else(|| versions.first().cloned())
+}
+
+/// `▶ Bitcoin Core · Build   ✓ 4m 12s` header row of a log section.
+fn section_header(section: &Section, font: &egui::FontId) -> egui::text::LayoutJob {
+    let arrow = if section.collapsed { "▶" } else { "▼" };
+    let (mark, color) = match section.status {
+        StageStatus::Running => ("⏳", pal::TERM_WARNING),
+        StageStatus::Succeeded => ("✓", pal::TERM_TEXT),
+        StageStatus::Failed => ("✗", pal::TERM_ERROR),
+    };
+    let format = |color| egui::TextFormat::simple(font.clone(), color);
+
+    let mut job = egui::text::LayoutJob::default();
+    job.append(
+        &format!("{arrow} {}   ", section.title),
+        0.0,
+        format(pal::TERM_HEADER),
+    );
+    job.append(
+        &format!("{mark} {}", format_duration(section.elapsed().as_secs())),
+        0.0,
+        format(color),
+    );
+    job
+}
+
+/// One log line with its ANSI styling.  Unstyled text takes the line's
+/// severity colour, or the stderr colour for a `stderr` line; `highlight`
+/// marks a search match.
+fn styled_line(
+    line: &str,
+    font: &egui::FontId,
+    stderr: bool,
+    highlight: Option<egui::Color32>,
+) -> egui::text::LayoutJob {
+    let default_color = match classify(line) {
+        Some(Severity::Error) => pal::TERM_ERROR,
+        Some(Severity::Warning) => pal::TERM_WARNING,
+        None if stderr => pal::TERM_STDERR,
+        None => pal::TERM_TEXT,
+    };
+    let rgb = |c: ansi::Color| {
+        let (r, g, b) = c.to_rgb();
+        egui::Color32::from_rgb(r, g, b)
+    };
+
+    let mut job = egui::text::LayoutJob::default();
+    for (text, style) in ansi::spans(line) {
+        let mut format =
+            egui::TextFormat::simple(font.clone(), style.fg.map_or(default_color, rgb));
+        format.italics = style.italic;
+        if style.underline {
+            format.underline = egui::Stroke::new(1.0, format.color);
+        }
+        if let Some(bg) = highlight.or(style.bg.map(rgb)) {
+            format.background = bg;
+        }
+        job.append(text, 0.0, format);
+    }
+    job
+}
+
+/// Open a file or folder with the desktop's default handler.
+fn open_path(path: &Path) -> std::io::Result<()> {
+    let opener = if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+    std::process::Command::new(opener)
+        .arg(path)
+        .spawn()
+        .map(|_| ())
+}
+
 /// macOS-style filled accent button.
 fn accent_button(label: &str) -> egui::Button<'_> {
     egui::Button::new(
@@ -870,6 +1811,11 @@ impl eframe::App for BitForgeApp {
         self.drain_messages();
         self.render_modal(ctx);
 
+        if let Some(rect) = ctx.input(|i| i.viewport().inner_rect) {
+            self.window_size = Some([rect.width(), rect.height()]);
+        }
+        self.persist_settings();
+
         // ── Status bar ────────────────────────────────────────────────────────
         egui::TopBottomPanel::bottom("status_bar")
             .frame(egui::Frame {
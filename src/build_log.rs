@@ -0,0 +1,242 @@
+// src/build_log.rs
+//
+// Full, untrimmed log of each build, written to
+// `<build_dir>/logs/build-<YYYYMMDD-HHMMSS>-<target>.log`.
+//
+// `compile_selection` routes its messages through `tee`, which forwards
+// every `AppMessage` to the UI unchanged and appends the `Log` / `Output`
+// text to the file with a timestamp per line, as plain text unless colours
+// are kept.  Lines a command wrote to stderr carry `STDERR_TAG` after the
+// timestamp:
+//
+//     [14:02:07] -- Configuring done
+//     [14:02:09] [stderr] src/init.cpp:12: error: ...
+//
+// The UI's in-memory log is trimmed; this file is not, so the start of a
+// long failing build is never lost.  Only the newest `MAX_LOG_FILES` logs
+// are kept.
+
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, Sender};
+
+use anyhow::{Context, Result};
+use tokio::task::JoinHandle;
+
+use crate::ansi;
+use crate::history::{format_clock, format_file_stamp, format_timestamp, now_secs};
+use crate::messages::{AppMessage, Stream};
+use crate::options::Target;
+use crate::process::ResourceUsage;
+
+const LOG_DIR: &str = "logs";
+/// Marks a line written to stderr, after the timestamp.
+pub const STDERR_TAG: &str = "[stderr] ";
+/// Older build logs beyond this many are deleted when a new one starts.
+const MAX_LOG_FILES: usize = 30;
+
+pub struct BuildLog {
+    path: PathBuf,
+    file: BufWriter<File>,
+    /// Current unterminated line; a bare `\r` rewinds it like a terminal.
+    line: String,
+    /// Whether stderr output went into `line`.
+    line_stderr: bool,
+    /// A `\r` arrived after the last text of `line`: the next character
+    /// other than a line break replaces it.
+    rewind: bool,
+    /// Write ANSI colour codes through rather than stripping them.
+    keep_colors: bool,
+}
+
+impl BuildLog {
+    /// Create a new log for a build of `target` started at `started_at`,
+    /// pruning the oldest logs first.
+    pub fn create(
+        build_dir: &Path,
+        target: Target,
+        started_at: u64,
+        keep_colors: bool,
+    ) -> Result<Self> {
+        let dir = build_dir.join(LOG_DIR);
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+        rotate(&dir);
+
+        let name = format!(
+            "build-{}-{}.log",
+            format_file_stamp(started_at),
+            target.label().to_lowercase()
+        );
+        let path = dir.join(name);
+        let file =
+            File::create(&path).with_context(|| format!("Failed to create {}", path.display()))?;
+
+        let mut log = Self {
+            path,
+            file: BufWriter::new(file),
+            line: String::new(),
+            line_stderr: false,
+            rewind: false,
+            keep_colors,
+        };
+        writeln!(
+            log.file,
+            "# BitForge build log — {} started {}",
+            target.label(),
+            format_timestamp(started_at)
+        )
+        .ok();
+        Ok(log)
+    }
+
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append output text, from stderr if `stderr`.  Complete lines are
+    /// written with a timestamp; progress lines overwritten by `\r` keep
+    /// only their final state.
+    fn write_text(&mut self, text: &str, stderr: bool) {
+        let text = if self.keep_colors {
+            Cow::Borrowed(text)
+        } else {
+            ansi::strip(text)
+        };
+        for ch in text.chars() {
+            match ch {
+                '\r' => self.rewind = true,
+                '\n' => self.flush_line(),
+                c => {
+                    if self.rewind {
+                        self.line.clear();
+                        self.line_stderr = false;
+                        self.rewind = false;
+                    }
+                    self.line.push(c);
+                    self.line_stderr |= stderr;
+                }
+            }
+        }
+    }
+
+    fn flush_line(&mut self) {
+        let tag = if self.line_stderr { STDERR_TAG } else { "" };
+        let clock = format_clock(now_secs());
+        writeln!(self.file, "[{clock}] {tag}{}", self.line).ok();
+        self.line.clear();
+        self.line_stderr = false;
+        self.rewind = false;
+    }
+
+    /// Write any partial last line and close the file.
+    fn finish(mut self) -> PathBuf {
+        if !self.line.is_empty() {
+            self.flush_line();
+        }
+        self.file.flush().ok();
+        self.path
+    }
+}
+
+/// Delete the oldest `build-*.log` files so that, with the one about to be
+/// created, at most `MAX_LOG_FILES` remain.  Names sort chronologically.
+fn rotate(dir: &Path) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut logs: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with("build-") && n.ends_with(".log"))
+        })
+        .collect();
+    if logs.len() < MAX_LOG_FILES {
+        return;
+    }
+    logs.sort();
+    for old in &logs[..=logs.len() - MAX_LOG_FILES] {
+        std::fs::remove_file(old).ok();
+    }
+}
+
+// ─── Tee ──────────────────────────────────────────────────────────────────────
+
+/// Interpose `log` between a job and the UI.  Returns the sender the job
+/// should use; the task finishes — yielding the log's path and the total
+/// `Usage` of the job's commands — once every clone of that sender has
+/// been dropped.
+pub fn tee(
+    log: BuildLog,
+    ui_tx: Sender<AppMessage>,
+) -> (Sender<AppMessage>, JoinHandle<(PathBuf, ResourceUsage)>) {
+    let (job_tx, job_rx) = std::sync::mpsc::channel::<AppMessage>();
+    let handle = tokio::task::spawn_blocking(move || forward(log, &job_rx, &ui_tx));
+    (job_tx, handle)
+}
+
+fn forward(
+    mut log: BuildLog,
+    job_rx: &Receiver<AppMessage>,
+    ui_tx: &Sender<AppMessage>,
+) -> (PathBuf, ResourceUsage) {
+    let mut usage = ResourceUsage::default();
+    for msg in job_rx {
+        if let AppMessage::Usage(command) = &msg {
+            usage.add(command);
+        }
+        let output = match &msg {
+            AppMessage::Log(text) => Some((text, false)),
+            AppMessage::Output { stream, text, .. } => Some((text, *stream == Stream::Stderr)),
+            _ => None,
+        };
+        if let Some((text, stderr)) = output {
+            log.write_text(text, stderr);
+            // Keep the file current for search while the build runs.
+            log.file.flush().ok();
+        }
+        ui_tx.send(msg).ok();
+    }
+    (log.finish(), usage)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The lines a fresh log holds, without timestamps, after writing
+    /// each chunk of stdout in turn.
+    fn written(chunks: &[&str]) -> Vec<String> {
+        let dir = std::env::temp_dir().join(format!("bitforge-build-log-{}", std::process::id()));
+        let mut log =
+            BuildLog::create(&dir, Target::Bitcoin, now_secs(), false).expect("log is created");
+        for chunk in chunks {
+            log.write_text(chunk, false);
+        }
+        let path = log.finish();
+        let text = std::fs::read_to_string(&path).expect("log is readable");
+        std::fs::remove_dir_all(&dir).ok();
+        text.lines()
+            .skip(1)
+            .map(|line| {
+                line.split_once("] ")
+                    .map_or(line, |(_, rest)| rest)
+                    .to_owned()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn carriage_returns_keep_the_final_state() {
+        assert_eq!(written(&["a\rb\n"]), ["b"]);
+        assert_eq!(written(&["a\r", "b\n"]), ["b"]);
+        assert_eq!(written(&["a\r\n"]), ["a"]);
+        assert_eq!(written(&["a\r", "\n"]), ["a"]);
+        assert_eq!(written(&["10%\r20%\r", "done"]), ["done"]);
+    }
+}
@@ -0,0 +1,746 @@
+// src/cli.rs
+//
+// Headless command-line front-end for build boxes and SSH sessions.
+//
+//   bitcoin-compiler versions [--target T]
+//   bitcoin-compiler deps     [selection flags] [--yes | --no]
+//   bitcoin-compiler build    [selection flags] [build flags] [--dry-run] [--yes | --no]
+//   bitcoin-compiler tui      [selection flags] [build flags]
+//
+// The subcommands run exactly the same background tasks as the GUI
+// (`fetch_*_versions`, `check_dependencies_task`, `compile_selection`) on
+// the same channels; this module only replaces the egui window with a
+// stdout printer and answers `ConfirmRequest`s from flags.  `tui` hands
+// off to the full-screen terminal interface in `tui.rs`.
+
+use std::future::Future;
+use std::io::{IsTerminal, Write};
+use std::path::PathBuf;
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender};
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use tokio::runtime::Runtime;
+use tokio::task::JoinHandle;
+
+use crate::ansi;
+use crate::compiler::{compile_selection, plan_selection};
+use crate::deps::check_dependencies_task;
+use crate::env_setup::{brew_override, brew_prefix, find_brew, setup_build_environment};
+use crate::github::{fetch_bitcoin_versions, fetch_electrs_versions};
+use crate::history::format_duration;
+use crate::messages::{AppMessage, ConfirmRequest, Stream};
+use crate::options::{BuildOptions, BuildSelection, Target};
+use crate::process::{self, Priority};
+use crate::retry;
+use crate::settings::{default_build_dir, Settings};
+use crate::tui::{self, TuiConfig};
+
+/// Everything succeeded.
+pub const EXIT_OK: i32 = 0;
+/// The task ran but failed (build error, dependencies missing, network).
+pub const EXIT_FAILED: i32 = 1;
+/// The command line could not be parsed.
+pub const EXIT_USAGE: i32 = 2;
+
+const USAGE: &str = "\
+Usage: bitcoin-compiler [COMMAND] [OPTIONS]
+
+Without a command the graphical interface is started.
+
+Commands:
+  versions   List the latest stable release tags
+  deps       Check (and optionally install) build dependencies
+  build      Compile the selected target
+  tui        Full-screen terminal interface (for SSH sessions)
+  help       Show this message
+
+Selection options (deps, build, tui, versions):
+  --target <bitcoin|electrs|both>   What to build [default: bitcoin; versions: both]
+  --version <TAG>                   Version of the single selected target
+  --bitcoin-version <TAG>           Bitcoin Core version [default: latest]
+  --electrs-version <TAG>           Electrs version [default: latest]
+  --wallet                          Enable the Bitcoin Core wallet
+  --zmq                             Enable ZMQ notifications
+  --toolchain <NAME>                rustup toolchain for Electrs
+
+Build options (build, tui):
+  -j, --jobs <N>                    Parallel jobs [default: CPU count - 1]
+  --out <DIR>                       Build directory [default: ~/Downloads/bitcoin_builds]
+  --priority <P>                    normal, background (reduced CPU and I/O priority)
+                                    or adaptive (background, and fewer jobs while the
+                                    machine is busy) [default: as saved by the GUI]
+  --dry-run                         (build) Print the directories, environment and
+                                    every command, without running anything
+
+Network options (deps, build, tui, versions):
+  --attempts <N>                    Tries for each clone or GitHub request before
+                                    giving up on a dropped connection, timeout or
+                                    server error [default: 3, or as saved]
+
+Environment options (deps, build, tui):
+  --brew <PATH>                     Homebrew to use: its brew binary or prefix
+                                    [default: $BITFORGE_BREW, as saved, or the
+                                    first found in the usual places]
+
+Prompts:
+  -y, --yes                         Answer every confirmation with yes
+  -n, --no                          Answer every confirmation with no
+                                    (without either, ask on a terminal, else no)
+
+Exit status: 0 success, 1 failure, 2 usage error.
+";
+
+// ─── Arguments ────────────────────────────────────────────────────────────────
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Command {
+    Versions,
+    Deps,
+    Build,
+    Tui,
+    Help,
+}
+
+/// How to answer `ConfirmRequest`s.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Answer {
+    Yes,
+    No,
+    Ask,
+}
+
+struct CliArgs {
+    command: Command,
+    target: Option<Target>,
+    version: Option<String>,
+    bitcoin_version: Option<String>,
+    electrs_version: Option<String>,
+    jobs: Option<usize>,
+    out: Option<PathBuf>,
+    priority: Option<Priority>,
+    attempts: Option<u32>,
+    brew: Option<String>,
+    dry_run: bool,
+    options: BuildOptions,
+    answer: Answer,
+}
+
+fn parse_args(args: &[String]) -> Result<CliArgs> {
+    let (command, rest) = args.split_first().context("missing command")?;
+    let command = match command.as_str() {
+        "versions" => Command::Versions,
+        "deps" => Command::Deps,
+        "build" => Command::Build,
+        "tui" => Command::Tui,
+        "help" | "-h" | "--help" => Command::Help,
+        other => bail!("unknown command {other:?}"),
+    };
+
+    let mut parsed = CliArgs {
+        command,
+        target: None,
+        version: None,
+        bitcoin_version: None,
+        electrs_version: None,
+        jobs: None,
+        out: None,
+        priority: None,
+        attempts: None,
+        brew: None,
+        dry_run: false,
+        options: BuildOptions::default(),
+        answer: Answer::Ask,
+    };
+
+    let mut iter = rest.iter();
+    while let Some(arg) = iter.next() {
+        // Accept both `--flag value` and `--flag=value`.
+        let (flag, inline) = match arg.split_once('=') {
+            Some((f, v)) if f.starts_with("--") => (f, Some(v.to_owned())),
+            _ => (arg.as_str(), None),
+        };
+        let mut value = || -> Result<String> {
+            inline
+                .clone()
+                .or_else(|| iter.next().cloned())
+                .with_context(|| format!("{flag} needs a value"))
+        };
+
+        match flag {
+            "--target" => parsed.target = Some(parse_target(&value()?)?),
+            "--version" => parsed.version = Some(value()?),
+            "--bitcoin-version" => parsed.bitcoin_version = Some(value()?),
+            "--electrs-version" => parsed.electrs_version = Some(value()?),
+            "-j" | "--jobs" => {
+                let v = value()?;
+                let jobs: usize = v.parse().with_context(|| format!("invalid job count {v:?}"))?;
+                if jobs == 0 {
+                    bail!("--jobs must be at least 1");
+                }
+                parsed.jobs = Some(jobs);
+            }
+            "--out" => parsed.out = Some(PathBuf::from(value()?)),
+            "--priority" => parsed.priority = Some(parse_priority(&value()?)?),
+            "--attempts" => {
+                let v = value()?;
+                let attempts: u32 = v
+                    .parse()
+                    .with_context(|| format!("invalid attempt count {v:?}"))?;
+                if attempts == 0 {
+                    bail!("--attempts must be at least 1");
+                }
+                parsed.attempts = Some(attempts);
+            }
+            "--brew" => parsed.brew = Some(value()?),
+            "--dry-run" => parsed.dry_run = true,
+            "--wallet" => parsed.options.wallet = true,
+            "--zmq" => parsed.options.zmq = true,
+            "--toolchain" => parsed.options.rust_toolchain = value()?,
+            "-y" | "--yes" | "-n" | "--no" => {
+                let answer = if matches!(flag, "-y" | "--yes") {
+                    Answer::Yes
+                } else {
+                    Answer::No
+                };
+                if parsed.answer != Answer::Ask && parsed.answer != answer {
+                    bail!("--yes and --no cannot be combined");
+                }
+                parsed.answer = answer;
+            }
+            "-h" | "--help" => parsed.command = Command::Help,
+            other => bail!("unknown option {other:?}"),
+        }
+    }
+
+    if parsed.version.is_some() && parsed.target == Some(Target::Both) {
+        bail!("--version is ambiguous with --target both; use --bitcoin-version / --electrs-version");
+    }
+    Ok(parsed)
+}
+
+fn parse_target(s: &str) -> Result<Target> {
+    Target::ALL
+        .into_iter()
+        .find(|t| t.label().eq_ignore_ascii_case(s))
+        .with_context(|| format!("unknown target {s:?} (expected bitcoin, electrs or both)"))
+}
+
+fn parse_priority(s: &str) -> Result<Priority> {
+    Priority::ALL
+        .into_iter()
+        .find(|p| p.label().eq_ignore_ascii_case(s))
+        .with_context(|| {
+            format!("unknown priority {s:?} (expected normal, background or adaptive)")
+        })
+}
+
+// ─── Entry point ──────────────────────────────────────────────────────────────
+
+/// Run a headless command.  Returns `None` when `args` is empty, meaning
+/// the caller should start the GUI; otherwise the process exit status.
+pub fn run(args: &[String], runtime: &Runtime) -> Option<i32> {
+    if args.is_empty() {
+        return None;
+    }
+
+    let parsed = match parse_args(args) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("error: {e:#}\nRun `bitcoin-compiler help` for usage.");
+            return Some(EXIT_USAGE);
+        }
+    };
+
+    retry::set_attempts(
+        parsed
+            .attempts
+            .or_else(|| Settings::load().ok()?.network_attempts)
+            .unwrap_or(retry::DEFAULT_ATTEMPTS),
+    );
+
+    let result = match parsed.command {
+        Command::Help => {
+            print!("{USAGE}");
+            return Some(EXIT_OK);
+        }
+        Command::Versions => cmd_versions(&parsed, runtime),
+        Command::Deps => cmd_deps(&parsed, runtime),
+        Command::Build => cmd_build(&parsed, runtime),
+        Command::Tui => cmd_tui(&parsed, runtime),
+    };
+
+    Some(match result {
+        Ok(true) => EXIT_OK,
+        Ok(false) => EXIT_FAILED,
+        Err(e) => {
+            eprintln!("error: {e:#}");
+            EXIT_FAILED
+        }
+    })
+}
+
+// ─── Commands ─────────────────────────────────────────────────────────────────
+
+fn cmd_versions(args: &CliArgs, runtime: &Runtime) -> Result<bool> {
+    let target = args.target.unwrap_or(Target::Both);
+    if target.includes_bitcoin() {
+        let versions = fetch_logged(
+            runtime,
+            |tx| async move { fetch_bitcoin_versions(&tx).await },
+        )?;
+        println!("Bitcoin Core:");
+        versions.iter().for_each(|v| println!("  {v}"));
+    }
+    if target.includes_electrs() {
+        let versions = fetch_logged(
+            runtime,
+            |tx| async move { fetch_electrs_versions(&tx).await },
+        )?;
+        println!("Electrs:");
+        versions.iter().for_each(|v| println!("  {v}"));
+    }
+    Ok(true)
+}
+
+fn cmd_deps(args: &CliArgs, runtime: &Runtime) -> Result<bool> {
+    let settings = Settings::load().unwrap_or_default();
+    let brew = locate_brew(args, &settings)?
+        .context("Homebrew not found. Install it from https://brew.sh or pass --brew")?;
+    let env = setup_build_environment(runtime.block_on(brew_prefix(&brew)).as_deref());
+    let selection = resolve_selection(args, runtime)?;
+    process::set_priority(args.priority.unwrap_or(settings.priority));
+
+    let (msg_tx, msg_rx) = std::sync::mpsc::channel::<AppMessage>();
+    let (confirm_tx, confirm_rx) = std::sync::mpsc::channel::<ConfirmRequest>();
+    let task = runtime.spawn(check_dependencies_task(
+        brew, env, selection, msg_tx, confirm_tx,
+    ));
+
+    pump(runtime, args.answer, &msg_rx, &confirm_rx, task)?
+}
+
+fn cmd_build(args: &CliArgs, runtime: &Runtime) -> Result<bool> {
+    let settings = Settings::load().unwrap_or_default();
+    let brew_pfx = match locate_brew(args, &settings)? {
+        Some(brew) => runtime.block_on(brew_prefix(&brew)),
+        None => None,
+    };
+    let env = setup_build_environment(brew_pfx.as_deref());
+    let selection = resolve_selection(args, runtime)?;
+
+    let build_dir = args
+        .out
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(default_build_dir()));
+    let cores = args.jobs.unwrap_or_else(|| {
+        std::thread::available_parallelism()
+            .map(|n| n.get().saturating_sub(1).max(1))
+            .unwrap_or(1)
+    });
+
+    let log_colors = settings.keep_log_colors;
+    process::set_priority(args.priority.unwrap_or(settings.priority));
+
+    if args.dry_run {
+        let plan = runtime.block_on(plan_selection(&selection, &build_dir, cores, &env))?;
+        print!("{plan}");
+        return Ok(true);
+    }
+
+    let (msg_tx, msg_rx) = std::sync::mpsc::channel::<AppMessage>();
+    let (_confirm_tx, confirm_rx) = std::sync::mpsc::channel::<ConfirmRequest>();
+    let task = runtime.spawn(async move {
+        compile_selection(&selection, &build_dir, cores, &env, log_colors, &msg_tx)
+            .await
+            .is_ok()
+    });
+
+    pump(runtime, args.answer, &msg_rx, &confirm_rx, task)
+}
+
+/// Start the terminal interface.  Unset flags fall back to the settings
+/// saved by the GUI, so both front-ends start from the same place.
+fn cmd_tui(args: &CliArgs, runtime: &Runtime) -> Result<bool> {
+    // A malformed settings file is not worth refusing to start over.
+    let settings = Settings::load().unwrap_or_default();
+    let brew = locate_brew(args, &settings)?;
+    let brew_pfx = match &brew {
+        Some(brew) => runtime.block_on(brew_prefix(brew)),
+        None => None,
+    };
+    let env = setup_build_environment(brew_pfx.as_deref());
+    process::set_priority(args.priority.unwrap_or(settings.priority));
+    let max_cores = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1);
+    let target = args.target.unwrap_or(settings.target);
+    let (bitcoin_version, electrs_version) = match target {
+        Target::Bitcoin => (args.version.clone(), None),
+        Target::Electrs => (None, args.version.clone()),
+        Target::Both => (None, None),
+    };
+    let options = if args.options == BuildOptions::default() {
+        settings.options
+    } else {
+        args.options.clone()
+    };
+
+    let cfg = TuiConfig {
+        target,
+        options,
+        cores: args
+            .jobs
+            .or(settings.cores)
+            .unwrap_or_else(|| max_cores.saturating_sub(1))
+            .clamp(1, max_cores),
+        max_cores,
+        build_dir: args
+            .out
+            .clone()
+            .or_else(|| settings.build_dir.map(PathBuf::from))
+            .unwrap_or_else(|| PathBuf::from(default_build_dir())),
+        bitcoin_version: args
+            .bitcoin_version
+            .clone()
+            .or(bitcoin_version)
+            .or(settings.bitcoin_version),
+        electrs_version: args
+            .electrs_version
+            .clone()
+            .or(electrs_version)
+            .or(settings.electrs_version),
+        brew,
+        env,
+        log_colors: settings.keep_log_colors,
+    };
+    tui::run(cfg, runtime)?;
+    Ok(true)
+}
+
+/// The `brew` binary: `--brew`, else `BITFORGE_BREW` or the location saved
+/// by the GUI, else the first one found in the usual places.
+fn locate_brew(args: &CliArgs, settings: &Settings) -> Result<Option<String>> {
+    let custom = args
+        .brew
+        .clone()
+        .or_else(|| brew_override(settings.brew_path.as_deref()));
+    find_brew(custom.as_deref())
+}
+
+/// Fill in the selection, fetching the newest release for any project
+/// whose version was not given.
+fn resolve_selection(args: &CliArgs, runtime: &Runtime) -> Result<BuildSelection> {
+    let target = args.target.unwrap_or(Target::Bitcoin);
+    let single = |project_version: &Option<String>| {
+        project_version.clone().or_else(|| {
+            (target != Target::Both)
+                .then(|| args.version.clone())
+                .flatten()
+        })
+    };
+
+    let mut bitcoin_version = single(&args.bitcoin_version).unwrap_or_default();
+    if target.includes_bitcoin() && bitcoin_version.is_empty() {
+        let versions = fetch_logged(
+            runtime,
+            |tx| async move { fetch_bitcoin_versions(&tx).await },
+        );
+        bitcoin_version = latest(versions, "Bitcoin Core")?;
+    }
+    let mut electrs_version = single(&args.electrs_version).unwrap_or_default();
+    if target.includes_electrs() && electrs_version.is_empty() {
+        let versions = fetch_logged(
+            runtime,
+            |tx| async move { fetch_electrs_versions(&tx).await },
+        );
+        electrs_version = latest(versions, "Electrs")?;
+    }
+
+    Ok(BuildSelection {
+        target,
+        bitcoin_version,
+        electrs_version,
+        options: args.options.clone(),
+    })
+}
+
+/// Run a version fetch, printing what it logs (its retries) to stderr as it
+/// happens so stdout stays just the result.
+fn fetch_logged<F>(
+    runtime: &Runtime,
+    fetch: impl FnOnce(Sender<AppMessage>) -> F,
+) -> Result<Vec<String>>
+where
+    F: Future<Output = Result<Vec<String>>> + Send + 'static,
+{
+    let (tx, rx) = std::sync::mpsc::channel::<AppMessage>();
+    let task = runtime.spawn(fetch(tx));
+    // Ends once the fetch has finished and dropped its sender.
+    for msg in rx {
+        if let AppMessage::Log(text) = msg {
+            write_output(std::io::stderr().lock(), &text);
+        }
+    }
+    runtime.block_on(task).context("background task panicked")?
+}
+
+fn latest(versions: Result<Vec<String>>, project: &str) -> Result<String> {
+    let newest = versions?
+        .into_iter()
+        .next()
+        .with_context(|| format!("GitHub lists no stable {project} releases"))?;
+    eprintln!("Using latest {project} release: {newest}");
+    Ok(newest)
+}
+
+// ─── Message pump ─────────────────────────────────────────────────────────────
+
+/// Print the task's messages and answer its prompts until it finishes.
+///
+/// Ctrl-C aborts the task, and dropping it kills the process group of the
+/// command it was running — each command has its own group, so the
+/// terminal's SIGINT no longer reaches cmake or the compilers directly.  A
+/// second Ctrl-C exits at once, e.g. while a prompt waits for an answer.
+fn pump<T>(
+    runtime: &Runtime,
+    answer: Answer,
+    msg_rx: &Receiver<AppMessage>,
+    confirm_rx: &Receiver<ConfirmRequest>,
+    task: JoinHandle<T>,
+) -> Result<T> {
+    let abort = task.abort_handle();
+    let interrupt = runtime.spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            eprintln!("\nInterrupted — stopping the running command…");
+            abort.abort();
+        }
+        if tokio::signal::ctrl_c().await.is_ok() {
+            std::process::exit(EXIT_FAILED);
+        }
+    });
+
+    loop {
+        match msg_rx.recv_timeout(Duration::from_millis(50)) {
+            Ok(msg) => print_message(msg),
+            // All senders gone: the task has finished.
+            Err(RecvTimeoutError::Disconnected) => break,
+            Err(RecvTimeoutError::Timeout) => {}
+        }
+        while let Ok(req) = confirm_rx.try_recv() {
+            answer_confirm(req, answer);
+        }
+        if task.is_finished() {
+            while let Ok(msg) = msg_rx.try_recv() {
+                print_message(msg);
+            }
+            break;
+        }
+    }
+    interrupt.abort();
+    match runtime.block_on(task) {
+        Ok(value) => Ok(value),
+        Err(e) if e.is_cancelled() => bail!("interrupted"),
+        Err(e) => Err(e).context("background task panicked"),
+    }
+}
+
+fn print_message(msg: AppMessage) {
+    match msg {
+        AppMessage::Log(text) => write_output(std::io::stdout().lock(), &text),
+        // Commands' stderr stays on stderr, so `2>` separates diagnostics.
+        AppMessage::Output {
+            stream: Stream::Stderr,
+            text,
+            ..
+        } => write_output(std::io::stderr().lock(), &text),
+        AppMessage::Output { text, .. } => write_output(std::io::stdout().lock(), &text),
+        AppMessage::Stalled {
+            command,
+            silent_for: Some(silent_for),
+        } => eprintln!(
+            "\nnote: no output for {} from: {command}",
+            format_duration(silent_for.as_secs())
+        ),
+        AppMessage::ShowDialog {
+            title,
+            message,
+            is_error,
+        } => {
+            let level = if is_error { "error" } else { "note" };
+            eprintln!("\n{level}: {title}\n{message}\n");
+        }
+        AppMessage::BuildFailed { message, diagnosis } => {
+            eprintln!("\nerror: Compilation Failed\n{message}\n");
+            if let Some(diagnosis) = diagnosis {
+                eprintln!("{}", diagnosis.report());
+            }
+        }
+        AppMessage::Stalled { .. }
+        | AppMessage::Progress(_)
+        | AppMessage::BitcoinVersionsLoaded(_)
+        | AppMessage::ElectrsVersionsLoaded(_)
+        | AppMessage::InstalledPackages(_)
+        | AppMessage::StageStarted(_)
+        | AppMessage::BuildFinished { .. }
+        | AppMessage::LogFile(_)
+        | AppMessage::Usage(_)
+        | AppMessage::BuildRecorded(_)
+        | AppMessage::TaskDone => {}
+    }
+}
+
+/// Keep colour for a terminal, plain text for pipes and CI logs.
+fn write_output(mut out: impl Write + IsTerminal, text: &str) {
+    let text = if out.is_terminal() {
+        text.into()
+    } else {
+        ansi::strip(text)
+    };
+    out.write_all(text.as_bytes()).ok();
+    out.flush().ok();
+}
+
+fn answer_confirm(req: ConfirmRequest, answer: Answer) {
+    let ticked = || req.items.iter().map(|item| item.checked).collect::<Vec<_>>();
+
+    eprintln!("\n? {}\n{}", req.title, req.message);
+    for item in &req.items {
+        let mark = if item.checked { 'x' } else { ' ' };
+        eprintln!("  [{mark}] {} — {}", item.label, item.detail);
+    }
+
+    let yes = match answer {
+        Answer::Yes => true,
+        Answer::No => false,
+        Answer::Ask if std::io::stdin().is_terminal() => {
+            eprint!("Proceed? [y/N] ");
+            let mut line = String::new();
+            std::io::stdin().read_line(&mut line).ok();
+            matches!(line.trim().to_ascii_lowercase().as_str(), "y" | "yes")
+        }
+        Answer::Ask => {
+            eprintln!("(not a terminal and neither --yes nor --no given: answering no)");
+            false
+        }
+    };
+    eprintln!("→ {}", if yes { "yes" } else { "no" });
+
+    let reply = yes.then(ticked);
+    req.response_tx.send(reply).ok();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<CliArgs> {
+        let args: Vec<String> = args.iter().map(|a| (*a).to_owned()).collect();
+        parse_args(&args)
+    }
+
+    fn exit_code(args: &[&str]) -> Option<i32> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("runtime");
+        let args: Vec<String> = args.iter().map(|a| (*a).to_owned()).collect();
+        run(&args, &runtime)
+    }
+
+    #[test]
+    fn parses_every_build_flag() {
+        let args = parse(&[
+            "build",
+            "--target",
+            "electrs",
+            "--version=v0.10.9",
+            "-j",
+            "8",
+            "--out",
+            "/tmp/builds",
+            "--priority",
+            "Background",
+            "--attempts=5",
+            "--toolchain",
+            "1.80.0",
+            "--brew",
+            "/home/linuxbrew/.linuxbrew",
+            "--dry-run",
+            "--yes",
+        ])
+        .expect("valid");
+        assert_eq!(args.command, Command::Build);
+        assert_eq!(args.target, Some(Target::Electrs));
+        assert_eq!(args.version.as_deref(), Some("v0.10.9"));
+        assert_eq!(args.jobs, Some(8));
+        assert_eq!(args.out, Some(PathBuf::from("/tmp/builds")));
+        assert_eq!(args.priority, Some(Priority::Background));
+        assert_eq!(args.attempts, Some(5));
+        assert_eq!(args.options.rust_toolchain, "1.80.0");
+        assert_eq!(args.brew.as_deref(), Some("/home/linuxbrew/.linuxbrew"));
+        assert!(args.dry_run);
+        assert_eq!(args.answer, Answer::Yes);
+    }
+
+    #[test]
+    fn defaults_without_flags() {
+        let args = parse(&["deps"]).expect("valid");
+        assert_eq!(args.command, Command::Deps);
+        assert_eq!(args.target, None);
+        assert_eq!(args.jobs, None);
+        assert_eq!(args.priority, None);
+        assert_eq!(args.options, BuildOptions::default());
+        assert!(!args.dry_run);
+        assert_eq!(args.answer, Answer::Ask);
+    }
+
+    #[test]
+    fn answers() {
+        assert_eq!(parse(&["deps", "-n"]).expect("valid").answer, Answer::No);
+        assert_eq!(
+            parse(&["deps", "--yes", "-y"]).expect("valid").answer,
+            Answer::Yes
+        );
+        assert!(parse(&["deps", "--yes", "--no"]).is_err());
+        assert!(parse(&["deps", "-n", "-y"]).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_flags() {
+        let invalid: &[&[&str]] = &[
+            &["build", "--jobs", "eight"],
+            &["build", "-j", "0"],
+            &["build", "--jobs"],
+            &["build", "--attempts", "0"],
+            &["build", "--attempts=many"],
+            &["build", "--priority", "urgent"],
+            &["build", "--target", "litecoin"],
+            &["build", "--target", "both", "--version", "v29.0"],
+            &["build", "--frobnicate"],
+            &["frobnicate"],
+            &[],
+        ];
+        for args in invalid {
+            assert!(parse(args).is_err(), "{args:?} parsed");
+        }
+    }
+
+    #[test]
+    fn help_anywhere() {
+        assert_eq!(parse(&["help"]).expect("valid").command, Command::Help);
+        assert_eq!(
+            parse(&["build", "--help"]).expect("valid").command,
+            Command::Help
+        );
+    }
+
+    #[test]
+    fn exit_codes() {
+        assert_eq!(exit_code(&[]), None);
+        assert_eq!(exit_code(&["help"]), Some(EXIT_OK));
+        assert_eq!(exit_code(&["frobnicate"]), Some(EXIT_USAGE));
+        assert_eq!(exit_code(&["build", "--jobs", "x"]), Some(EXIT_USAGE));
+        assert_eq!(exit_code(&["deps", "--yes", "--no"]), Some(EXIT_USAGE));
+    }
+}
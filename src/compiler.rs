@@ -2,8 +2,10 @@
 //
 // compile_bitcoin  — clone, cmake configure, cmake build, copy binaries.
 // compile_electrs  — clone, cargo build --release, copy binary.
+// plan_selection   — the same steps as text, for a dry run.
 //
-// Bitcoin Core v29+ uses CMake exclusively (autotools removed upstream).
+// Bitcoin Core v29+ uses CMake exclusively (autotools removed upstream),
+// and so does this module: older tags are refused (`MIN_BITCOIN_VERSION`).
 // The critical env requirement: PKG_CONFIG_PATH must point at Homebrew's
 // pkgconfig directories so cmake can find libevent, sqlite, etc. via
 // pkg-config. Without this, cmake falls back to exhaustive try_compile
@@ -12,32 +14,238 @@
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::mpsc::Sender;
+use std::time::Duration;
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 
+use crate::build_log::{self, BuildLog};
+use crate::deps::{electrs_rustc_minimum, format_version, parse_tool_version};
+use crate::diagnose::diagnose;
+use crate::github::parse_semver;
+use crate::history::{self, BuildRecord, Outcome};
 use crate::messages::{log_msg, AppMessage};
-use crate::process::{probe, run_command};
+use crate::options::{BuildOptions, BuildSelection, Target};
+use crate::retry::{retry, transient_git};
+use crate::toolchain::{
+    ensure_toolchain, install_toolchain_command, resolve_toolchain, rustup_version,
+    toolchain_installed, validate_toolchain, ToolchainSource,
+};
+use crate::process::{self, probe, run_command, CommandSpec, Priority};
+
+/// Oldest Bitcoin Core release the CMake-only build supports.  Shared with
+/// the version list and the dependency check so neither offers what the
+/// build cannot do.
+pub const MIN_BITCOIN_VERSION: (u32, u32, u32) = (29, 0, 0);
 
 const BITCOIN_REPO: &str = "https://github.com/bitcoin/bitcoin.git";
 const ELECTRS_REPO: &str = "https://github.com/romanz/electrs.git";
 const SEP: &str = "============================================================";
+/// A shallow clone that has not finished by now is stuck on the network.
+const CLONE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+/// Longest silence tolerated before a command is considered hung: git
+/// prints progress continuously, cmake configure reports every check, and
+/// a single compile step (or link with LTO) is the slowest of all.
+const CLONE_IDLE_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+const CONFIGURE_IDLE_TIMEOUT: Duration = Duration::from_secs(20 * 60);
+const BUILD_IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
 
 // ─── Public compile functions ─────────────────────────────────────────────────
 
+/// Build everything in `selection` (Bitcoin Core first, then Electrs),
+/// driving the progress bar and reporting the outcome as a dialog.
+///
+/// Returns the output directories on success.  Shared by the GUI and the
+/// headless front-ends so every entry point builds identically, and every
+/// run is appended to the build history.  `log_colors` keeps ANSI colour
+/// in the build's log file.
+pub async fn compile_selection(
+    selection: &BuildSelection,
+    build_dir: &Path,
+    cores: usize,
+    env: &HashMap<String, String>,
+    log_colors: bool,
+    tx: &Sender<AppMessage>,
+) -> Result<Vec<PathBuf>> {
+    let target = selection.target;
+    let both = target == Target::Both;
+    let mut output_dirs: Vec<PathBuf> = Vec::new();
+    let started_at = history::now_secs();
+    let paused_before = process::paused_time();
+
+    // Everything the build says also goes to its own log file.  Without
+    // one (e.g. an unwritable build dir) the build still runs; the
+    // directory error then resurfaces as the build failure.
+    let (job_tx, log_task) = match BuildLog::create(build_dir, target, started_at, log_colors) {
+        Ok(log) => {
+            log_msg(tx, &format!("📝 Full log: {}\n", log.path().display()));
+            tx.send(AppMessage::LogFile(log.path().to_path_buf())).ok();
+            let (job_tx, task) = build_log::tee(log, tx.clone());
+            (job_tx, Some(task))
+        }
+        Err(e) => {
+            log_msg(tx, &format!("⚠️  Not writing a log file: {e:#}\n"));
+            (tx.clone(), None)
+        }
+    };
+
+    job_tx.send(AppMessage::Progress(0.05)).ok();
+
+    let priority = process::priority();
+    if priority.is_background() {
+        log_msg(
+            &job_tx,
+            "🐢 Background priority: commands run at reduced CPU and I/O priority.\n",
+        );
+    }
+    let cores = match reduced_jobs(cores, priority) {
+        Some((jobs, load)) => {
+            log_msg(
+                &job_tx,
+                &format!("🐢 Machine busy (load {load:.1}) — using {jobs} of {cores} jobs.\n"),
+            );
+            jobs
+        }
+        None => cores,
+    };
+
+    let result: Result<()> = async {
+        let tx = &job_tx;
+        if target.includes_bitcoin() {
+            tx.send(AppMessage::Progress(0.1)).ok();
+            let dir = compile_bitcoin(
+                &selection.bitcoin_version,
+                build_dir,
+                cores,
+                &selection.options,
+                env,
+                tx,
+            )
+            .await?;
+            output_dirs.push(dir);
+            tx.send(AppMessage::Progress(if both { 0.5 } else { 0.95 })).ok();
+        }
+
+        if target.includes_electrs() {
+            tx.send(AppMessage::Progress(if both { 0.55 } else { 0.1 })).ok();
+            let dir = compile_electrs(
+                &selection.electrs_version,
+                build_dir,
+                cores,
+                &selection.options,
+                env,
+                tx,
+            )
+            .await?;
+            output_dirs.push(dir);
+        }
+        Ok(())
+    }
+    .await;
+
+    let diagnosis = result.as_ref().err().and_then(diagnose);
+    if let Err(e) = &result {
+        log_msg(&job_tx, &format!("\n❌ Compilation failed: {e}\n"));
+        if let Some(diagnosis) = &diagnosis {
+            log_msg(&job_tx, &format!("\n{}", diagnosis.report()));
+        }
+    }
+    job_tx
+        .send(AppMessage::BuildFinished {
+            success: result.is_ok(),
+        })
+        .ok();
+    // Closing the job's sender ends the tee once it has written everything.
+    drop(job_tx);
+    let (log_file, usage) = match log_task {
+        Some(task) => task.await.ok().unzip(),
+        None => (None, None),
+    };
+
+    let (bitcoin_commit, electrs_commit) = source_commits(selection, build_dir, env).await;
+    let record = BuildRecord {
+        target,
+        bitcoin_version: target
+            .includes_bitcoin()
+            .then(|| selection.bitcoin_version.clone()),
+        bitcoin_commit,
+        electrs_version: target
+            .includes_electrs()
+            .then(|| selection.electrs_version.clone()),
+        electrs_commit,
+        options: selection.options.clone(),
+        cores,
+        build_dir: build_dir.to_path_buf(),
+        started_at,
+        finished_at: history::now_secs(),
+        paused_secs: (process::paused_time() - paused_before).as_secs(),
+        outcome: if result.is_ok() {
+            Outcome::Succeeded
+        } else {
+            Outcome::Failed
+        },
+        error: result
+            .as_ref()
+            .err()
+            .and_then(|e| e.to_string().lines().next().map(str::to_owned)),
+        output_dirs: output_dirs.clone(),
+        log_file,
+        usage,
+    };
+    // Failing to record is only worth a warning; the build is unaffected.
+    match history::append(record.clone()) {
+        Ok(note) => {
+            if let Some(note) = note {
+                log_msg(tx, &format!("⚠️  Build history: {note}\n"));
+            }
+            tx.send(AppMessage::BuildRecorded(Box::new(record))).ok();
+        }
+        Err(e) => log_msg(tx, &format!("⚠️  Could not save build history: {e:#}\n")),
+    }
+
+    if let Err(e) = result {
+        tx.send(AppMessage::BuildFailed {
+            message: e.to_string(),
+            diagnosis,
+        })
+        .ok();
+        return Err(e);
+    }
+
+    tx.send(AppMessage::Progress(1.0)).ok();
+    let dirs_list = output_dirs
+        .iter()
+        .map(|d| format!("• {}", d.display()))
+        .collect::<Vec<_>>()
+        .join("\n");
+    tx.send(AppMessage::ShowDialog {
+        title: "Compilation Complete".into(),
+        message: format!(
+            "✅ {} compiled successfully!\n\nBinaries saved to:\n{dirs_list}",
+            target.label()
+        ),
+        is_error: false,
+    })
+    .ok();
+
+    Ok(output_dirs)
+}
+
 pub async fn compile_bitcoin(
     version: &str,
     build_dir: &Path,
     cores: usize,
+    options: &BuildOptions,
     env: &HashMap<String, String>,
     tx: &Sender<AppMessage>,
 ) -> Result<PathBuf> {
+    check_bitcoin_supported(version)?;
+    stage(tx, "Bitcoin Core", "Source");
     log_msg(
         tx,
         &format!("\n{SEP}\nCOMPILING BITCOIN CORE {version}\n{SEP}\n"),
     );
 
-    let version_clean = version.trim_start_matches('v');
-    let src_dir = build_dir.join(format!("bitcoin-{version_clean}"));
+    let src_dir = source_dir(build_dir, "bitcoin", version);
 
     tokio::fs::create_dir_all(build_dir)
         .await
@@ -59,19 +267,21 @@ pub async fn compile_bitcoin(
     // ── Step 2: cmake configure ───────────────────────────────────────────────
     //
     // Flags used (matching the official build-osx.md for v29+):
-    //   -DENABLE_WALLET=OFF   skip wallet (no Berkeley DB / SQLite needed)
+    //   -DENABLE_WALLET=OFF   skip wallet unless enabled (needs SQLite)
     //   -DENABLE_IPC=OFF      skip IPC (no capnp needed)
     //   -DBUILD_TESTS=OFF     skip test suite compilation
     //   -DBUILD_BENCH=OFF     skip benchmarks
     //   -DBUILD_GUI=OFF       skip Qt GUI
     //   -DWITH_MINIUPNPC=OFF  skip optional UPnP dep
     //   -DWITH_NATPMP=OFF     skip optional NAT-PMP dep
-    //   -DWITH_ZMQ=OFF        skip optional ZMQ dep
+    //   -DWITH_ZMQ=OFF        skip ZMQ unless enabled (needs ZeroMQ)
     //
-    // With wallet/IPC/tests/bench/GUI/optional-deps all disabled, the only
-    // required non-system dependency is libevent, which pkg-config finds
-    // instantly once PKG_CONFIG_PATH is set correctly.
+    // With the defaults, the only required non-system dependencies are
+    // Boost headers and libevent, which pkg-config finds instantly once
+    // PKG_CONFIG_PATH is set correctly.  deps.rs keeps its dependency
+    // matrix in step with these flags.
 
+    stage(tx, "Bitcoin Core", "Configure");
     log_msg(
         tx,
         "\n── Step 1/3: CMake configure ────────────────────────────────\n",
@@ -88,52 +298,26 @@ pub async fn compile_bitcoin(
 
     tx.send(AppMessage::Progress(0.2)).ok();
 
-    run_command(
-        "cmake -B build \
-            -DENABLE_WALLET=OFF \
-            -DENABLE_IPC=OFF \
-            -DBUILD_TESTS=OFF \
-            -DBUILD_BENCH=OFF \
-            -DBUILD_GUI=OFF \
-            -DWITH_MINIUPNPC=OFF \
-            -DWITH_NATPMP=OFF \
-            -DWITH_ZMQ=OFF",
-        Some(&src_dir),
-        &env,
-        tx,
-    )
-    .await
-    .context(
-        "cmake configure failed.\n\
-         Common causes:\n\
-         - libevent not installed: brew install libevent\n\
-         - cmake not installed:    brew install cmake\n\
-         - Xcode CLI tools missing: xcode-select --install",
-    )?;
+    run_command(&cmake_configure_command(&src_dir, options, &env), tx)
+        .await
+        .context("cmake configure failed")?;
 
     // ── Step 3: cmake build ───────────────────────────────────────────────────
+    stage(tx, "Bitcoin Core", "Build");
     log_msg(
         tx,
         &format!("\n── Step 2/3: Build ({cores} cores) ──────────────────────────────\n\n"),
     );
     tx.send(AppMessage::Progress(0.45)).ok();
 
-    // No --target flag: with BUILD_TESTS/BENCH/GUI/WALLET all OFF at configure
-    // time, cmake builds only the node binaries (bitcoind, bitcoin-cli, etc.).
-    // Listing targets explicitly breaks across versions — bitcoin-tx was
-    // removed in v29 and the set may change further.
-    run_command(
-        &format!("cmake --build build -j {cores}"),
-        Some(&src_dir),
-        &env,
-        tx,
-    )
-    .await
-    .context("cmake build failed")?;
+    run_command(&cmake_build_command(&src_dir, cores, &env), tx)
+        .await
+        .context("cmake build failed")?;
 
     tx.send(AppMessage::Progress(0.9)).ok();
 
     // ── Step 4: copy binaries ─────────────────────────────────────────────────
+    stage(tx, "Bitcoin Core", "Copy binaries");
     log_msg(
         tx,
         "\n── Step 3/3: Copying binaries ───────────────────────────────\n",
@@ -144,9 +328,7 @@ pub async fn compile_bitcoin(
     let bin_dir = src_dir.join("build").join("bin");
     let candidates = collect_executables(&bin_dir).await;
 
-    let output_dir = build_dir
-        .join("binaries")
-        .join(format!("bitcoin-{version_clean}"));
+    let output_dir = output_dir(build_dir, "bitcoin", version);
 
     let copied = copy_binaries(&output_dir, &candidates, tx).await?;
 
@@ -182,15 +364,17 @@ pub async fn compile_electrs(
     version: &str,
     build_dir: &Path,
     cores: usize,
+    options: &BuildOptions,
     env: &HashMap<String, String>,
     tx: &Sender<AppMessage>,
 ) -> Result<PathBuf> {
+    stage(tx, "Electrs", "Source & toolchain");
     log_msg(
         tx,
         &format!("\n{SEP}\nCOMPILING ELECTRS {version}\n{SEP}\n"),
     );
 
-    let env = cargo_env(env);
+    let mut env = cargo_env(env, None);
 
     log_msg(tx, "\n🔍 Verifying Rust installation...\n");
     match probe(&["cargo", "--version"], &env).await {
@@ -208,12 +392,7 @@ pub async fn compile_electrs(
         }
     }
 
-    if let Some(v) = probe(&["rustc", "--version"], &env).await {
-        log_msg(tx, &format!("✓ Rustc: {v}\n"));
-    }
-
-    let version_clean = version.trim_start_matches('v');
-    let src_dir = build_dir.join(format!("electrs-{version_clean}"));
+    let src_dir = source_dir(build_dir, "electrs", version);
 
     tokio::fs::create_dir_all(build_dir)
         .await
@@ -221,6 +400,53 @@ pub async fn compile_electrs(
 
     clone_or_update(&src_dir, build_dir, version, ELECTRS_REPO, tx, &env).await?;
 
+    // ── Toolchain selection ───────────────────────────────────────────────────
+    // Needs the checkout, since the tag may ship its own rust-toolchain file.
+    let rustup = rustup_version(&env).await.is_some();
+    match resolve_toolchain(Some(&options.rust_toolchain), &src_dir).await {
+        Some((toolchain, source)) if rustup => {
+            ensure_toolchain(&toolchain, &env, tx).await?;
+            log_msg(
+                tx,
+                &format!("🦀 Rust toolchain: {toolchain} ({})\n", source.label()),
+            );
+            env = cargo_env(&env, Some(&toolchain));
+        }
+        Some((toolchain, ToolchainSource::Pinned)) => {
+            return Err(anyhow::anyhow!(
+                "Rust toolchain {toolchain} is pinned, but rustup is not installed.\n\
+                 Install rustup from https://rustup.rs or clear the pin."
+            ));
+        }
+        Some((toolchain, ToolchainSource::ToolchainFile)) => log_msg(
+            tx,
+            &format!(
+                "⚠️  Checkout requests toolchain {toolchain}, but rustup is not installed — \
+                 using the Rust on PATH\n"
+            ),
+        ),
+        None if rustup => log_msg(tx, "🦀 Rust toolchain: rustup default\n"),
+        None => log_msg(tx, "🦀 Rust toolchain: not managed by rustup\n"),
+    }
+    if let Some(v) = probe(&["rustc", "--version"], &env).await {
+        log_msg(tx, &format!("✓ Rustc: {v}\n"));
+        // The dependency check could only see the pin, not the checkout's
+        // rust-toolchain file; this is the toolchain cargo will use.
+        if let (Some(found), Some(minimum)) =
+            (parse_tool_version(&v), electrs_rustc_minimum(version))
+        {
+            if found < minimum {
+                bail!(
+                    "Rust {} is older than Electrs {version} requires ({}).\n\
+                     Run `rustup update`, or pin a newer toolchain.",
+                    format_version(found),
+                    format_version(minimum)
+                );
+            }
+        }
+    }
+
+    stage(tx, "Electrs", "Build");
     log_msg(
         tx,
         &format!("\n🔧 Building Electrs with Cargo ({cores} jobs)...\n"),
@@ -231,14 +457,9 @@ pub async fn compile_electrs(
 
     tx.send(AppMessage::Progress(0.3)).ok();
 
-    run_command(
-        &format!("cargo build --release --jobs {cores}"),
-        Some(&src_dir),
-        &env,
-        tx,
-    )
-    .await
-    .context("cargo build --release failed")?;
+    run_command(&cargo_build_command(&src_dir, cores, &env), tx)
+        .await
+        .context("cargo build --release failed")?;
 
     tx.send(AppMessage::Progress(0.85)).ok();
 
@@ -250,9 +471,8 @@ pub async fn compile_electrs(
         ));
     }
 
-    let output_dir = build_dir
-        .join("binaries")
-        .join(format!("electrs-{version_clean}"));
+    let output_dir = output_dir(build_dir, "electrs", version);
+    stage(tx, "Electrs", "Copy binary");
     copy_binaries(&output_dir, &[binary], tx).await?;
 
     log_msg(
@@ -267,6 +487,315 @@ pub async fn compile_electrs(
     Ok(output_dir)
 }
 
+// ─── Dry run ──────────────────────────────────────────────────────────────────
+
+/// What `compile_selection` would do for `selection`, without doing any of
+/// it: directories, each project's environment, and every command in the
+/// order it would run.  Existing checkouts and installed toolchains are
+/// inspected (read-only) so the plan skips the steps the build would skip.
+pub async fn plan_selection(
+    selection: &BuildSelection,
+    build_dir: &Path,
+    cores: usize,
+    env: &HashMap<String, String>,
+) -> Result<String> {
+    let target = selection.target;
+    let priority = process::priority();
+    let mut lines = vec![
+        format!("{SEP}\nBUILD PLAN — dry run, nothing is executed\n{SEP}\n"),
+        format!("Target:          {}", target.label()),
+        format!("Build directory: {}", build_dir.display()),
+        format!("Priority:        {}", priority.label()),
+    ];
+    let cores = match reduced_jobs(cores, priority) {
+        Some((jobs, load)) => {
+            lines.push(format!(
+                "Jobs:            {jobs} (of {cores}; machine busy, load {load:.1})"
+            ));
+            jobs
+        }
+        None => {
+            lines.push(format!("Jobs:            {cores}"));
+            cores
+        }
+    };
+
+    if target.includes_bitcoin() {
+        let version = &selection.bitcoin_version;
+        validate_version_tag(version)?;
+        check_bitcoin_supported(version)?;
+        let src_dir = source_dir(build_dir, "bitcoin", version);
+        let env = bitcoin_env(env);
+        let mut commands = Vec::new();
+
+        lines.push(format!("\n── Bitcoin Core {version} ──"));
+        lines.push(format!("Source:    {}", src_dir.display()));
+        let clone = plan_clone(&src_dir, build_dir, version, BITCOIN_REPO, &env, &mut lines).await;
+        commands.extend(clone);
+        commands.push(cmake_configure_command(&src_dir, &selection.options, &env));
+        commands.push(cmake_build_command(&src_dir, cores, &env));
+
+        let output_dir = output_dir(build_dir, "bitcoin", version);
+        lines.push(format!("Binaries:  {}", output_dir.display()));
+        plan_commands(&commands, &mut lines);
+        lines.push(format!(
+            "  copy the executables in {} to the binaries directory",
+            src_dir.join("build/bin").display()
+        ));
+        plan_env(&env, &mut lines);
+    }
+
+    if target.includes_electrs() {
+        let version = &selection.electrs_version;
+        validate_version_tag(version)?;
+        let src_dir = source_dir(build_dir, "electrs", version);
+        let mut env = cargo_env(env, None);
+        let mut commands = Vec::new();
+
+        lines.push(format!("\n── Electrs {version} ──"));
+        if probe(&["cargo", "--version"], &env).await.is_none() {
+            lines.push("⚠️  Cargo not found in PATH — the build would stop here.".to_owned());
+        }
+        lines.push(format!("Source:    {}", src_dir.display()));
+        let clone = plan_clone(&src_dir, build_dir, version, ELECTRS_REPO, &env, &mut lines).await;
+        commands.extend(clone);
+
+        let rustup = rustup_version(&env).await.is_some();
+        match resolve_toolchain(Some(&selection.options.rust_toolchain), &src_dir).await {
+            Some((toolchain, source)) if rustup => {
+                validate_toolchain(&toolchain)?;
+                lines.push(format!("Toolchain: {toolchain} ({})", source.label()));
+                if !toolchain_installed(&toolchain, &env).await {
+                    commands.push(install_toolchain_command(&toolchain, &env));
+                }
+                env = cargo_env(&env, Some(&toolchain));
+            }
+            Some((toolchain, ToolchainSource::Pinned)) => lines.push(format!(
+                "⚠️  Rust toolchain {toolchain} is pinned, but rustup is not installed — \
+                 the build would stop here."
+            )),
+            Some((toolchain, ToolchainSource::ToolchainFile)) => lines.push(format!(
+                "Toolchain: the Rust on PATH (checkout requests {toolchain}, but rustup is not installed)"
+            )),
+            None if rustup && !src_dir.exists() => lines.push(
+                "Toolchain: the checkout's rust-toolchain file if it has one, else rustup's default \
+                 (decided after cloning)"
+                    .to_owned(),
+            ),
+            None if rustup => lines.push("Toolchain: rustup default".to_owned()),
+            None => lines.push("Toolchain: not managed by rustup".to_owned()),
+        }
+        commands.push(cargo_build_command(&src_dir, cores, &env));
+
+        let output_dir = output_dir(build_dir, "electrs", version);
+        lines.push(format!("Binaries:  {}", output_dir.display()));
+        plan_commands(&commands, &mut lines);
+        lines.push(format!(
+            "  copy {} to the binaries directory",
+            src_dir.join("target/release/electrs").display()
+        ));
+        plan_env(&env, &mut lines);
+    }
+
+    lines.push(String::new());
+    Ok(lines.join("\n"))
+}
+
+/// Note what the checkout step would do; the clone command, if it would
+/// run one.
+async fn plan_clone(
+    src_dir: &Path,
+    build_dir: &Path,
+    version: &str,
+    repo_url: &str,
+    env: &HashMap<String, String>,
+    lines: &mut Vec<String>,
+) -> Option<CommandSpec> {
+    let (status, clone) = match checkout_tag(src_dir, env).await {
+        Some(tag) if tag == version => (format!("already at {version} — not cloned again"), false),
+        Some(tag) => (format!("at '{tag}' — would be cloned afresh"), true),
+        None => ("not cloned yet".to_owned(), true),
+    };
+    lines.push(format!("           {status}"));
+    clone.then(|| clone_command(src_dir, build_dir, version, repo_url, env))
+}
+
+fn plan_commands(commands: &[CommandSpec], lines: &mut Vec<String>) {
+    lines.push("Commands:".to_owned());
+    for command in commands {
+        lines.extend(command.describe().lines().map(|line| format!("  {line}")));
+    }
+}
+
+/// The complete environment commands would get, sorted by name.  Values
+/// of anything that looks like a credential are hidden, since plans get
+/// pasted into bug reports.
+fn plan_env(env: &HashMap<String, String>, lines: &mut Vec<String>) {
+    let secret = |key: &str| {
+        let key = key.to_ascii_uppercase();
+        ["TOKEN", "SECRET", "PASSWORD", "KEY"]
+            .iter()
+            .any(|word| key.contains(word))
+    };
+    let mut vars: Vec<_> = env.iter().collect();
+    vars.sort();
+    lines.push("Environment:".to_owned());
+    lines.extend(vars.into_iter().map(|(key, value)| {
+        let value = if secret(key) { "(hidden)" } else { value };
+        format!("  {key}={value}")
+    }));
+}
+
+// ─── Stages ───────────────────────────────────────────────────────────────────
+
+/// Start a new collapsible log section, e.g. "Bitcoin Core · Configure".
+fn stage(tx: &Sender<AppMessage>, project: &str, name: &str) {
+    tx.send(AppMessage::StageStarted(format!("{project} · {name}")))
+        .ok();
+}
+
+// ─── Priority ─────────────────────────────────────────────────────────────────
+
+/// For an adaptive-priority build, fewer jobs than `cores` if the rest of
+/// the machine already keeps some of the CPUs busy (by the one-minute load
+/// average, sampled before we add any) — with that load.
+fn reduced_jobs(cores: usize, priority: Priority) -> Option<(usize, f64)> {
+    if priority != Priority::Adaptive {
+        return None;
+    }
+    let load = process::load_average()?;
+    let cpus = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1);
+    let free = cpus.saturating_sub(load.round() as usize).max(1);
+    (free < cores).then_some((free, load))
+}
+
+// ─── Build history ────────────────────────────────────────────────────────────
+
+/// HEAD commits of the Bitcoin Core and Electrs checkouts the selection
+/// used, where they exist.
+async fn source_commits(
+    selection: &BuildSelection,
+    build_dir: &Path,
+    env: &HashMap<String, String>,
+) -> (Option<String>, Option<String>) {
+    let target = selection.target;
+    let bitcoin = if target.includes_bitcoin() {
+        let dir = source_dir(build_dir, "bitcoin", &selection.bitcoin_version);
+        source_commit(&dir, env).await
+    } else {
+        None
+    };
+    let electrs = if target.includes_electrs() {
+        let dir = source_dir(build_dir, "electrs", &selection.electrs_version);
+        source_commit(&dir, env).await
+    } else {
+        None
+    };
+    (bitcoin, electrs)
+}
+
+/// Abbreviated HEAD commit of a checkout, if it exists.
+async fn source_commit(src_dir: &Path, env: &HashMap<String, String>) -> Option<String> {
+    if !src_dir.exists() {
+        return None;
+    }
+    probe(
+        &[
+            "git",
+            "-C",
+            &src_dir.to_string_lossy(),
+            "rev-parse",
+            "--short=12",
+            "HEAD",
+        ],
+        env,
+    )
+    .await
+}
+
+/// Checkout directory for `project` at `version`, e.g. `bitcoin-29.0`.
+fn source_dir(build_dir: &Path, project: &str, version: &str) -> PathBuf {
+    build_dir.join(format!("{project}-{}", version.trim_start_matches('v')))
+}
+
+/// Where `project`'s binaries are copied, e.g. `binaries/bitcoin-29.0`.
+fn output_dir(build_dir: &Path, project: &str, version: &str) -> PathBuf {
+    build_dir
+        .join("binaries")
+        .join(format!("{project}-{}", version.trim_start_matches('v')))
+}
+
+// ─── CMake flags ──────────────────────────────────────────────────────────────
+
+fn cmake_configure_args(options: &BuildOptions) -> Vec<String> {
+    let on_off = |enabled: bool| if enabled { "ON" } else { "OFF" };
+    vec![
+        "-B".to_owned(),
+        "build".to_owned(),
+        format!("-DENABLE_WALLET={}", on_off(options.wallet)),
+        "-DENABLE_IPC=OFF".to_owned(),
+        "-DBUILD_TESTS=OFF".to_owned(),
+        "-DBUILD_BENCH=OFF".to_owned(),
+        "-DBUILD_GUI=OFF".to_owned(),
+        "-DWITH_MINIUPNPC=OFF".to_owned(),
+        "-DWITH_NATPMP=OFF".to_owned(),
+        format!("-DWITH_ZMQ={}", on_off(options.zmq)),
+    ]
+}
+
+// ─── Commands ─────────────────────────────────────────────────────────────────
+//
+// Every command a build runs, shared by the build itself and its dry run
+// so the two cannot drift apart.
+
+/// Shallow clone of `version` into `src_dir`.
+fn clone_command(
+    src_dir: &Path,
+    build_dir: &Path,
+    version: &str,
+    repo_url: &str,
+    env: &HashMap<String, String>,
+) -> CommandSpec {
+    CommandSpec::new("git", env)
+        .args(["clone", "--progress", "--depth", "1"])
+        .args(["--branch", version, repo_url])
+        .arg(src_dir)
+        .in_dir(build_dir)
+        .timeout(CLONE_TIMEOUT)
+        .idle_timeout(CLONE_IDLE_TIMEOUT)
+}
+
+fn cmake_configure_command(
+    src_dir: &Path,
+    options: &BuildOptions,
+    env: &HashMap<String, String>,
+) -> CommandSpec {
+    CommandSpec::new("cmake", env)
+        .args(cmake_configure_args(options))
+        .in_dir(src_dir)
+        .idle_timeout(CONFIGURE_IDLE_TIMEOUT)
+}
+
+// No --target flag: with BUILD_TESTS/BENCH/GUI/WALLET all OFF at configure
+// time, cmake builds only the node binaries (bitcoind, bitcoin-cli, etc.).
+// Listing targets explicitly breaks across versions — bitcoin-tx was
+// removed in v29 and the set may change further.
+fn cmake_build_command(src_dir: &Path, cores: usize, env: &HashMap<String, String>) -> CommandSpec {
+    CommandSpec::new("cmake", env)
+        .args(["--build", "build", "-j", &cores.to_string()])
+        .in_dir(src_dir)
+        .idle_timeout(BUILD_IDLE_TIMEOUT)
+}
+
+fn cargo_build_command(src_dir: &Path, cores: usize, env: &HashMap<String, String>) -> CommandSpec {
+    CommandSpec::new("cargo", env)
+        .args(["build", "--release", "--jobs", &cores.to_string()])
+        .in_dir(src_dir)
+        .idle_timeout(BUILD_IDLE_TIMEOUT)
+}
+
 // ─── Environment builders ─────────────────────────────────────────────────────
 
 /// Environment for Bitcoin Core cmake builds.
@@ -281,14 +810,17 @@ fn bitcoin_env(base: &HashMap<String, String>) -> HashMap<String, String> {
     // Bitcoin Core cmake finds libevent and other Homebrew deps via pkg-config.
     // Without these paths cmake runs silent try_compile probes for every lib,
     // stalling the configure step for 10+ minutes with no visible output.
-    let homebrew_dirs = [
-        "/opt/homebrew/lib/pkgconfig",
-        "/opt/homebrew/share/pkgconfig",
-        "/usr/local/lib/pkgconfig",
-        "/usr/local/share/pkgconfig",
-    ];
-
-    let mut pcp: Vec<String> = homebrew_dirs.iter().map(|s| s.to_string()).collect();
+    // HOMEBREW_PREFIX is exported by setup_build_environment from the
+    // discovered brew install (macOS or Linuxbrew alike).
+    let mut pcp: Vec<String> = env
+        .get("HOMEBREW_PREFIX")
+        .map(|pfx| {
+            vec![
+                format!("{pfx}/lib/pkgconfig"),
+                format!("{pfx}/share/pkgconfig"),
+            ]
+        })
+        .unwrap_or_default();
     if let Some(existing) = env.get("PKG_CONFIG_PATH") {
         for part in existing.split(':').filter(|p| !p.is_empty()) {
             if !pcp.contains(&part.to_string()) {
@@ -298,25 +830,35 @@ fn bitcoin_env(base: &HashMap<String, String>) -> HashMap<String, String> {
     }
     env.insert("PKG_CONFIG_PATH".to_owned(), pcp.join(":"));
 
-    // Suppress colours but do NOT set TERM=dumb (cmake buffers output when dumb).
+    // Force colour — the log panel renders ANSI — but do NOT set TERM=dumb
+    // (cmake buffers output when dumb).  CMAKE_COLOR_DIAGNOSTICS (cmake
+    // 3.24+) adds -fcolor-diagnostics, since the compiler only sees a pipe.
     env.remove("TERM");
-    env.insert("NO_COLOR".to_owned(), "1".to_owned());
-    env.insert("CLICOLOR".to_owned(), "0".to_owned());
-    env.insert("CLICOLOR_FORCE".to_owned(), "0".to_owned());
+    env.remove("NO_COLOR");
+    env.insert("CLICOLOR".to_owned(), "1".to_owned());
+    env.insert("CLICOLOR_FORCE".to_owned(), "1".to_owned());
+    env.insert("CMAKE_COLOR_DIAGNOSTICS".to_owned(), "ON".to_owned());
     env.insert("GIT_PROGRESS_DELAY".to_owned(), "0".to_owned());
 
     env
 }
 
 /// Environment for Cargo / Rust builds (Electrs).
-fn cargo_env(base: &HashMap<String, String>) -> HashMap<String, String> {
+///
+/// `toolchain` selects a rustup toolchain via `RUSTUP_TOOLCHAIN`, which the
+/// rustup proxies honour for cargo, rustc and every build script.
+fn cargo_env(base: &HashMap<String, String>, toolchain: Option<&str>) -> HashMap<String, String> {
     let mut env = base.clone();
-    env.insert("NO_COLOR".to_owned(), "1".to_owned());
+    if let Some(toolchain) = toolchain {
+        env.insert("RUSTUP_TOOLCHAIN".to_owned(), toolchain.to_owned());
+    }
+    env.remove("NO_COLOR");
     env.insert("TERM".to_owned(), "dumb".to_owned());
-    env.insert("CLICOLOR".to_owned(), "0".to_owned());
-    env.insert("CLICOLOR_FORCE".to_owned(), "0".to_owned());
+    env.insert("CLICOLOR".to_owned(), "1".to_owned());
+    env.insert("CLICOLOR_FORCE".to_owned(), "1".to_owned());
     env.insert("GIT_PROGRESS_DELAY".to_owned(), "0".to_owned());
-    env.insert("CARGO_TERM_COLOR".to_owned(), "never".to_owned());
+    // The log panel renders ANSI colour; cargo passes it on to rustc.
+    env.insert("CARGO_TERM_COLOR".to_owned(), "always".to_owned());
     env.insert("CARGO_TERM_PROGRESS_WHEN".to_owned(), "always".to_owned());
     env.insert("CARGO_TERM_PROGRESS_WIDTH".to_owned(), "60".to_owned());
     env
@@ -410,7 +952,8 @@ async fn copy_binaries(
 
 /// Shallow-clone `version` into `src_dir`, or verify an existing clone matches.
 ///
-/// If the directory exists at a different tag, remove and re-clone.
+/// If the directory exists at a different tag, remove and re-clone.  A
+/// clone cut short by the network is retried from scratch.
 /// do NOT add --filter=blob:none: a blobless clone defers file downloads
 /// to first access, causing cmake/cargo to stall silently fetching blobs.
 async fn clone_or_update(
@@ -423,21 +966,7 @@ async fn clone_or_update(
 ) -> Result<()> {
     validate_version_tag(version)?;
 
-    if src_dir.exists() {
-        let current_tag = probe(
-            &[
-                "git",
-                "-C",
-                &src_dir.to_string_lossy(),
-                "describe",
-                "--tags",
-                "--exact-match",
-            ],
-            env,
-        )
-        .await
-        .unwrap_or_default();
-
+    if let Some(current_tag) = checkout_tag(src_dir, env).await {
         if current_tag == version {
             log_msg(
                 tx,
@@ -464,17 +993,16 @@ async fn clone_or_update(
         "   (shallow clone — may take a few minutes for Bitcoin Core)\n\n",
     );
 
-    run_command(
-        &format!(
-            "git clone --progress --depth 1 --branch {} {} {}",
-            shell_quote(version),
-            shell_quote(repo_url),
-            shell_quote(&src_dir.to_string_lossy()),
-        ),
-        Some(build_dir),
-        env,
-        tx,
-    )
+    let clone = clone_command(src_dir, build_dir, version, repo_url, env);
+    retry("git clone", tx, transient_git, || async {
+        // An interrupted clone leaves a partial checkout git will not clone over.
+        if src_dir.exists() {
+            tokio::fs::remove_dir_all(src_dir)
+                .await
+                .with_context(|| format!("Failed to remove {}", src_dir.display()))?;
+        }
+        run_command(&clone, tx).await
+    })
     .await
     .context("git clone failed")?;
 
@@ -482,6 +1010,27 @@ async fn clone_or_update(
     Ok(())
 }
 
+/// The tag an existing checkout is at; empty if HEAD is not exactly on a
+/// tag, `None` without a checkout.
+async fn checkout_tag(src_dir: &Path, env: &HashMap<String, String>) -> Option<String> {
+    if !src_dir.exists() {
+        return None;
+    }
+    let tag = probe(
+        &[
+            "git",
+            "-C",
+            &src_dir.to_string_lossy(),
+            "describe",
+            "--tags",
+            "--exact-match",
+        ],
+        env,
+    )
+    .await;
+    Some(tag.unwrap_or_default())
+}
+
 // ─── Utilities ────────────────────────────────────────────────────────────────
 
 fn validate_version_tag(tag: &str) -> Result<()> {
@@ -497,6 +1046,15 @@ fn validate_version_tag(tag: &str) -> Result<()> {
     }
 }
 
-fn shell_quote(s: &str) -> String {
-    format!("'{}'", s.replace('\'', r"'\''"))
+/// Refuse Bitcoin Core tags older than `MIN_BITCOIN_VERSION`: they have no
+/// CMake build, and there is no autotools path here.
+fn check_bitcoin_supported(version: &str) -> Result<()> {
+    let found = parse_semver(version);
+    if found == (0, 0, 0) || found >= MIN_BITCOIN_VERSION {
+        return Ok(());
+    }
+    let (major, minor, _) = MIN_BITCOIN_VERSION;
+    Err(anyhow::anyhow!(
+        "Bitcoin Core {version} predates the CMake build; BitForge builds v{major}.{minor} and later"
+    ))
 }
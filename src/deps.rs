@@ -1,42 +1,588 @@
 // src/deps.rs
 //
-// Background task: check and optionally install all build dependencies.
+// Background task: check and optionally install the build dependencies
+// required by the selected target, versions and build options.
 
 use std::collections::HashMap;
 use std::sync::mpsc::Sender;
+use std::time::Duration;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use tokio::sync::oneshot;
 
-use crate::messages::{log_msg, AppMessage, ConfirmRequest};
-use crate::process::{probe, run_command};
-
-// Homebrew packages required for Bitcoin Core (autotools + cmake) and Electrs.
-const BREW_PACKAGES: &[&str] = &[
-    "automake",
-    "libtool",
-    "pkg-config",
-    "boost",
-    "miniupnpc",
-    "zeromq",
-    "sqlite",
-    "python",
-    "cmake",
-    "llvm",
-    "libevent",
-    "rocksdb",
-    "rust",
-    "git",
+use crate::github::parse_semver;
+use crate::messages::{log_msg, AppMessage, ConfirmItem, ConfirmRequest};
+use crate::options::{BuildOptions, BuildSelection};
+use crate::process::{capture, probe, run_command, CommandSpec};
+use crate::toolchain::{ensure_toolchain, rustup_version};
+
+/// Limit for the brew commands that only query what is installed.
+const BREW_QUERY_TIMEOUT: Duration = Duration::from_secs(120);
+
+// ─── Dependency matrix ────────────────────────────────────────────────────────
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Project {
+    Bitcoin,
+    Electrs,
+}
+
+/// One Homebrew formula and the builds that need it.
+struct BrewDep {
+    formula: &'static str,
+    projects: &'static [Project],
+    /// Inclusive lower bound on the project version, if any.
+    since: Option<(u32, u32, u32)>,
+    /// Exclusive upper bound on the project version, if any.
+    until: Option<(u32, u32, u32)>,
+    /// Build option that must be enabled for the formula to be needed.
+    option: Option<fn(&BuildOptions) -> bool>,
+    /// Shown next to the formula so the user knows why it is being asked for.
+    reason: &'static str,
+}
+
+/// Every Homebrew formula BitForge may need, keyed by project, version range
+/// and build option.  The Rust toolchain is checked separately by
+/// `check_rust_installation` because it may come from rustup rather than brew.
+///
+/// Bitcoin Core is only built with CMake (`MIN_BITCOIN_VERSION` and
+/// later), so there are no autotools entries.
+///
+/// Deliberately absent: miniupnpc (`-DWITH_MINIUPNPC=OFF`), python (tests
+/// only) and rocksdb (electrs builds its bundled copy by default).
+const BREW_DEPS: &[BrewDep] = &[
+    BrewDep {
+        formula: "git",
+        projects: &[Project::Bitcoin, Project::Electrs],
+        since: None,
+        until: None,
+        option: None,
+        reason: "clones the source at the selected tag",
+    },
+    BrewDep {
+        formula: "cmake",
+        projects: &[Project::Bitcoin],
+        since: None,
+        until: None,
+        option: None,
+        reason: "Bitcoin Core build system",
+    },
+    BrewDep {
+        formula: "pkgconf",
+        projects: &[Project::Bitcoin],
+        since: None,
+        until: None,
+        option: None,
+        reason: "locates libevent and other libraries without slow probing",
+    },
+    BrewDep {
+        formula: "boost",
+        projects: &[Project::Bitcoin],
+        since: None,
+        until: None,
+        option: None,
+        reason: "header-only Boost libraries used by bitcoind",
+    },
+    BrewDep {
+        formula: "libevent",
+        projects: &[Project::Bitcoin],
+        since: None,
+        until: None,
+        option: None,
+        reason: "networking and RPC server event loop",
+    },
+    BrewDep {
+        formula: "sqlite",
+        projects: &[Project::Bitcoin],
+        since: None,
+        until: None,
+        option: Some(|o| o.wallet),
+        reason: "descriptor wallet storage (wallet enabled)",
+    },
+    BrewDep {
+        formula: "zeromq",
+        projects: &[Project::Bitcoin],
+        since: None,
+        until: None,
+        option: Some(|o| o.zmq),
+        reason: "ZMQ notification interface (ZMQ enabled)",
+    },
+    BrewDep {
+        formula: "llvm",
+        projects: &[Project::Electrs],
+        since: None,
+        until: None,
+        option: None,
+        reason: "libclang for the RocksDB bindgen step",
+    },
 ];
 
+impl BrewDep {
+    fn applies(&self, project: Project, version: &str, options: &BuildOptions) -> bool {
+        self.projects.contains(&project)
+            && version_in_range(version, self.since, self.until)
+            && self.option.into_iter().all(|enabled| enabled(options))
+    }
+}
+
+/// The formulae the selected build needs, each with its reason, in matrix
+/// order and without duplicates.
+fn required_packages(selection: &BuildSelection) -> Vec<(&'static str, &'static str)> {
+    let wanted = selected_projects(selection);
+    BREW_DEPS
+        .iter()
+        .filter(|dep| {
+            wanted
+                .iter()
+                .any(|(project, version)| dep.applies(*project, version, &selection.options))
+        })
+        .map(|dep| (dep.formula, dep.reason))
+        .collect()
+}
+
+/// The projects the selection builds, paired with their version tags.
+fn selected_projects(selection: &BuildSelection) -> Vec<(Project, &str)> {
+    let mut wanted = Vec::with_capacity(2);
+    if selection.target.includes_bitcoin() {
+        wanted.push((Project::Bitcoin, selection.bitcoin_version.as_str()));
+    }
+    if selection.target.includes_electrs() {
+        wanted.push((Project::Electrs, selection.electrs_version.as_str()));
+    }
+    wanted
+}
+
+/// `since <= version < until`, treating unparseable tags (e.g. versions
+/// still loading) as the newest release.
+fn version_in_range(
+    version: &str,
+    since: Option<(u32, u32, u32)>,
+    until: Option<(u32, u32, u32)>,
+) -> bool {
+    let v = match parse_semver(version) {
+        (0, 0, 0) => (u32::MAX, 0, 0),
+        v => v,
+    };
+    since.into_iter().all(|min| v >= min) && until.into_iter().all(|max| v < max)
+}
+
+// ─── Package installation ─────────────────────────────────────────────────────
+
+/// Install `packages` with a single `brew install`, then re-query brew so
+/// the log shows a result for every package even when the batch fails
+/// part-way through.  Returns the versions of those now installed.
+async fn install_packages(
+    brew: &str,
+    packages: &[&str],
+    env: &HashMap<String, String>,
+    log_tx: &Sender<AppMessage>,
+) -> Result<HashMap<String, String>> {
+    log_msg(
+        log_tx,
+        &format!("\n📦 Installing {}...\n", packages.join(", ")),
+    );
+    let cmd = CommandSpec::new(brew, env).arg("install").args(packages);
+    let batch = run_command(&cmd, log_tx).await;
+
+    let installed = installed_versions(brew, packages, env, log_tx).await?;
+    log_msg(log_tx, "\nInstallation results:\n");
+    let mut failed: Vec<&str> = Vec::new();
+    for pkg in packages {
+        match installed.get(*pkg) {
+            Some(version) => log_msg(log_tx, &format!("  ✓ {pkg} {version}\n")),
+            None => {
+                log_msg(log_tx, &format!("  ❌ {pkg} - not installed\n"));
+                failed.push(pkg);
+            }
+        }
+    }
+
+    if !failed.is_empty() {
+        let reason = batch.err().map(|e| format!("\n\n{e}")).unwrap_or_default();
+        log_tx
+            .send(AppMessage::ShowDialog {
+                title: "Installation Failed".into(),
+                message: format!("Failed to install: {}{reason}", failed.join(", ")),
+                is_error: true,
+            })
+            .ok();
+    }
+    Ok(installed)
+}
+
+// ─── Installed package query ──────────────────────────────────────────────────
+
+/// Return `formula → version` for every formula in `packages` that brew
+/// reports as installed.  Missing formulae are simply absent from the map.
+///
+/// `brew list --versions a b c` prints one `name version…` line per
+/// installed formula and exits non-zero if any of them is missing, so the
+/// exit status is deliberately ignored.
+async fn installed_versions(
+    brew: &str,
+    packages: &[&str],
+    env: &HashMap<String, String>,
+    log_tx: &Sender<AppMessage>,
+) -> Result<HashMap<String, String>> {
+    if packages.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let cmd = CommandSpec::new(brew, env)
+        .args(["list", "--versions"])
+        .args(packages)
+        .timeout(BREW_QUERY_TIMEOUT);
+    let output = capture(&cmd, log_tx)
+        .await
+        .context("Failed to run brew list --versions")?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(parse_brew_versions(&stdout))
+}
+
+/// Parse `brew list --versions` output — `name version…` per line — into
+/// `formula → version`.  Lines without a version are skipped.
+fn parse_brew_versions(output: &str) -> HashMap<String, String> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut words = line.split_whitespace();
+            let name = words.next()?;
+            // Several kegs may be installed; the last one listed is current.
+            let version = words.last()?;
+            Some((name.to_owned(), version.to_owned()))
+        })
+        .collect()
+}
+
+// ─── Minimum tool versions ────────────────────────────────────────────────────
+
+/// How to bring an outdated tool up to date.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Upgrade {
+    /// `brew upgrade <formula>`.
+    Brew(&'static str),
+    /// `rustup update` when rustup manages the toolchain, else `brew upgrade rust`.
+    Rust,
+    /// Cannot be automated; the hint is shown in the log.
+    Manual(&'static str),
+}
+
+/// Minimum version of a tool for a project/version range.
+struct ToolMinimum {
+    tool: &'static str,
+    command: &'static [&'static str],
+    project: Project,
+    since: Option<(u32, u32, u32)>,
+    until: Option<(u32, u32, u32)>,
+    /// Restricts the entry to one flavour of the tool (e.g. clang vs GCC),
+    /// judged from the first line of its `--version` output.
+    flavour: Option<fn(&str) -> bool>,
+    minimum: (u32, u32, u32),
+    upgrade: Upgrade,
+}
+
+const CXX_UPGRADE: Upgrade = Upgrade::Manual(
+    "update Xcode Command Line Tools (softwareupdate --list) or install a newer gcc/llvm",
+);
+/// `rustup update` cannot move a pin such as `1.60.0` forward.
+const PINNED_RUST_UPGRADE: Upgrade =
+    Upgrade::Manual("pin a newer Rust toolchain, or clear the pin to use rustup's default");
+
+/// Minimums from Bitcoin Core's doc/dependencies.md and Electrs' MSRV.
+/// Entries for the same tool are combined by taking the highest minimum.
+/// Bitcoin Core rows cover every tag BitForge builds — the CMake build of
+/// `MIN_BITCOIN_VERSION` and later — so they need no version range.
+const TOOL_MINIMUMS: &[ToolMinimum] = &[
+    ToolMinimum {
+        tool: "git",
+        command: &["git", "--version"],
+        project: Project::Bitcoin,
+        since: None,
+        until: None,
+        flavour: None,
+        minimum: (2, 0, 0),
+        upgrade: Upgrade::Brew("git"),
+    },
+    ToolMinimum {
+        tool: "git",
+        command: &["git", "--version"],
+        project: Project::Electrs,
+        since: None,
+        until: None,
+        flavour: None,
+        minimum: (2, 0, 0),
+        upgrade: Upgrade::Brew("git"),
+    },
+    ToolMinimum {
+        tool: "cmake",
+        command: &["cmake", "--version"],
+        project: Project::Bitcoin,
+        since: None,
+        until: None,
+        flavour: None,
+        minimum: (3, 22, 0),
+        upgrade: Upgrade::Brew("cmake"),
+    },
+    ToolMinimum {
+        tool: "pkg-config",
+        command: &["pkg-config", "--version"],
+        project: Project::Bitcoin,
+        since: None,
+        until: None,
+        flavour: None,
+        minimum: (0, 29, 0),
+        upgrade: Upgrade::Brew("pkgconf"),
+    },
+    ToolMinimum {
+        tool: "C++ compiler",
+        command: &["c++", "--version"],
+        project: Project::Bitcoin,
+        since: None,
+        until: None,
+        flavour: Some(|v| v.starts_with("Apple clang")),
+        minimum: (15, 0, 0),
+        upgrade: CXX_UPGRADE,
+    },
+    ToolMinimum {
+        tool: "C++ compiler",
+        command: &["c++", "--version"],
+        project: Project::Bitcoin,
+        since: None,
+        until: None,
+        flavour: Some(|v| v.contains("clang") && !v.starts_with("Apple")),
+        minimum: (16, 0, 0),
+        upgrade: CXX_UPGRADE,
+    },
+    ToolMinimum {
+        tool: "C++ compiler",
+        command: &["c++", "--version"],
+        project: Project::Bitcoin,
+        since: None,
+        until: None,
+        flavour: Some(|v| !v.contains("clang")),
+        minimum: (11, 1, 0),
+        upgrade: CXX_UPGRADE,
+    },
+    ToolMinimum {
+        tool: "rustc",
+        command: &["rustc", "--version"],
+        project: Project::Electrs,
+        since: Some((0, 10, 0)),
+        until: None,
+        flavour: None,
+        minimum: (1, 63, 0),
+        upgrade: Upgrade::Rust,
+    },
+];
+
+/// Oldest rustc that builds Electrs `version`, if it has a minimum — for
+/// checking the toolchain a checkout selects once it has been cloned.
+#[must_use]
+pub fn electrs_rustc_minimum(version: &str) -> Option<(u32, u32, u32)> {
+    TOOL_MINIMUMS
+        .iter()
+        .filter(|m| m.project == Project::Electrs && m.upgrade == Upgrade::Rust)
+        .filter(|m| version_in_range(version, m.since, m.until))
+        .map(|m| m.minimum)
+        .max()
+}
+
+/// Extract the first `major.minor[.patch]` number from a `--version` line,
+/// e.g. `"cmake version 3.30.2"` or `"c++ (Ubuntu 13.2.0-4ubuntu3) 13.2.0"`.
+#[must_use]
+pub fn parse_tool_version(output: &str) -> Option<(u32, u32, u32)> {
+    output
+        .split(|c: char| c.is_whitespace() || c == '(' || c == ')')
+        .find_map(|word| {
+            let numeric: String = word
+                .chars()
+                .take_while(|c| c.is_ascii_digit() || *c == '.')
+                .collect();
+            let mut parts = numeric.split('.').map(|p| p.parse::<u32>().ok());
+            let major = parts.next()??;
+            let minor = parts.next()??;
+            let patch = parts.next().flatten().unwrap_or(0);
+            Some((major, minor, patch))
+        })
+}
+
+#[must_use]
+pub fn format_version((major, minor, patch): (u32, u32, u32)) -> String {
+    format!("{major}.{minor}.{patch}")
+}
+
+/// Probe every tool the selection depends on and compare it with its
+/// minimum.  Offers to upgrade the ones that fall short.
+///
+/// Returns `true` when every tool was found and is new enough.
+async fn check_tool_versions(
+    brew: &str,
+    selection: &BuildSelection,
+    env: &HashMap<String, String>,
+    log_tx: &Sender<AppMessage>,
+    confirm_tx: &Sender<ConfirmRequest>,
+) -> bool {
+    log_msg(log_tx, "\n=== Checking Tool Versions ===\n");
+
+    let wanted = selected_projects(selection);
+    let applicable: Vec<&ToolMinimum> = TOOL_MINIMUMS
+        .iter()
+        .filter(|m| {
+            wanted
+                .iter()
+                .any(|(p, v)| *p == m.project && version_in_range(v, m.since, m.until))
+        })
+        .collect();
+
+    let mut tools: Vec<&str> = Vec::new();
+    for m in &applicable {
+        if !tools.contains(&m.tool) {
+            tools.push(m.tool);
+        }
+    }
+
+    // rustc is asked through the toolchain that will build Electrs: a pin
+    // is selected with RUSTUP_TOOLCHAIN, exactly as `cargo_env` does.
+    // Without one the checkout's rust-toolchain file decides, which
+    // `compile_electrs` checks once it has cloned the tag.
+    let pinned = selection.options.rust_toolchain.trim();
+    let mut rust_env = env.clone();
+    if !pinned.is_empty() {
+        rust_env.insert("RUSTUP_TOOLCHAIN".to_owned(), pinned.to_owned());
+    }
+
+    let mut all_ok = true;
+    let mut upgrades: Vec<Upgrade> = Vec::new();
+
+    for tool in tools {
+        let entries: Vec<&&ToolMinimum> = applicable.iter().filter(|m| m.tool == tool).collect();
+        let Some(first) = entries.first() else {
+            continue;
+        };
+        let via_pin = first.upgrade == Upgrade::Rust && !pinned.is_empty();
+        let (probe_env, tool) = if via_pin {
+            (&rust_env, format!("{tool} ({pinned})"))
+        } else {
+            (env, tool.to_owned())
+        };
+
+        let Some(output) = probe(first.command, probe_env).await else {
+            log_msg(log_tx, &format!("  ❌ {tool} not found in PATH\n"));
+            all_ok = false;
+            continue;
+        };
+        let line = output.lines().next().unwrap_or_default();
+
+        let Some(found) = parse_tool_version(line) else {
+            log_msg(
+                log_tx,
+                &format!("  ⚠️  {tool}: could not parse version from {line:?}\n"),
+            );
+            continue;
+        };
+
+        let required = entries
+            .iter()
+            .filter(|m| m.flavour.into_iter().all(|matches| matches(line)))
+            .max_by_key(|m| m.minimum);
+
+        match required {
+            Some(m) if found < m.minimum => {
+                let upgrade = if via_pin {
+                    PINNED_RUST_UPGRADE
+                } else {
+                    m.upgrade
+                };
+                log_msg(
+                    log_tx,
+                    &format!(
+                        "  ❌ {tool} {} is older than the required {}\n",
+                        format_version(found),
+                        format_version(m.minimum),
+                    ),
+                );
+                if let Upgrade::Manual(hint) = upgrade {
+                    log_msg(log_tx, &format!("     → {hint}\n"));
+                }
+                if !upgrades.contains(&upgrade) {
+                    upgrades.push(upgrade);
+                }
+                all_ok = false;
+            }
+            Some(m) => log_msg(
+                log_tx,
+                &format!(
+                    "  ✓ {tool} {} (>= {})\n",
+                    format_version(found),
+                    format_version(m.minimum)
+                ),
+            ),
+            None => log_msg(log_tx, &format!("  ✓ {tool} {}\n", format_version(found))),
+        }
+    }
+
+    // ── Offer automated upgrades ──────────────────────────────────────────────
+    let formulae: Vec<&str> = upgrades
+        .iter()
+        .filter_map(|u| match u {
+            Upgrade::Brew(formula) => Some(*formula),
+            _ => None,
+        })
+        .collect();
+    let rust = upgrades.contains(&Upgrade::Rust);
+    if formulae.is_empty() && !rust {
+        return all_ok;
+    }
+
+    let rustup = probe(&["rustup", "--version"], env).await.is_some();
+    let mut commands: Vec<CommandSpec> = Vec::new();
+    if !formulae.is_empty() {
+        commands.push(CommandSpec::new(brew, env).arg("upgrade").args(&formulae));
+    }
+    if rust {
+        commands.push(if rustup {
+            CommandSpec::new("rustup", env).arg("update")
+        } else {
+            CommandSpec::new(brew, env).args(["upgrade", "rust"])
+        });
+    }
+
+    let listing: Vec<String> = commands.iter().map(CommandSpec::to_string).collect();
+    let message = format!(
+        "Some tools are older than the selected build requires.\n\nRun the following now?\n\n{}",
+        listing.join("\n")
+    );
+    if !ask_confirm(confirm_tx, "Upgrade Outdated Tools", &message).await {
+        log_msg(
+            log_tx,
+            "\n⚠️  Outdated tools not upgraded. Compilation may fail.\n",
+        );
+        return false;
+    }
+
+    let mut upgraded = true;
+    for cmd in &commands {
+        if let Err(e) = run_command(cmd, log_tx).await {
+            log_msg(log_tx, &format!("❌ Upgrade failed: {e}\n"));
+            upgraded = false;
+        }
+    }
+    // Manual-only upgrades still need the user's attention.
+    upgraded && upgrades.iter().all(|u| !matches!(u, Upgrade::Manual(_)))
+}
+
 // ─── Public entry point ───────────────────────────────────────────────────────
 
-/// Background task: check and (optionally) install all dependencies.
+/// Background task: check and (optionally) install the dependencies the
+/// selected build needs.
 ///
-/// Returns `true` when everything — including the Rust toolchain — is ready.
+/// Returns `true` when everything — including the Rust toolchain, if Electrs
+/// is selected — is ready.
 pub async fn check_dependencies_task(
     brew: String,
     env: HashMap<String, String>,
+    selection: BuildSelection,
     log_tx: Sender<AppMessage>,
     confirm_tx: Sender<ConfirmRequest>,
 ) -> Result<bool> {
@@ -44,93 +590,108 @@ pub async fn check_dependencies_task(
     log_msg(&log_tx, &format!("✓ Homebrew found at: {brew}\n"));
 
     // ── Check Homebrew packages ───────────────────────────────────────────────
-    log_msg(&log_tx, "\nChecking Homebrew packages...\n");
-
-    let mut missing: Vec<&str> = Vec::new();
-    for &pkg in BREW_PACKAGES {
-        // Use tokio::process::Command to avoid blocking a thread pool thread.
-        let ok = tokio::process::Command::new(&brew)
-            .args(["list", pkg])
-            .env_clear()
-            .envs(&env)
-            .output()
-            .await
-            .map(|o| o.status.success())
-            .unwrap_or(false);
+    log_msg(
+        &log_tx,
+        &format!(
+            "\nChecking Homebrew packages for target: {}...\n",
+            selection.target.label()
+        ),
+    );
 
-        if ok {
-            log_msg(&log_tx, &format!("  ✓ {pkg}\n"));
-        } else {
-            log_msg(&log_tx, &format!("  ❌ {pkg} - not installed\n"));
-            missing.push(pkg);
+    // One `brew list --versions` for the whole set instead of one Ruby
+    // process per formula.
+    let required = required_packages(&selection);
+    let names: Vec<&str> = required.iter().map(|(pkg, _)| *pkg).collect();
+    let mut installed = installed_versions(&brew, &names, &env, &log_tx).await?;
+
+    let mut missing: Vec<(&str, &str)> = Vec::new();
+    for &(pkg, reason) in &required {
+        match installed.get(pkg) {
+            Some(version) => log_msg(&log_tx, &format!("  ✓ {pkg} {version} — {reason}\n")),
+            None => {
+                log_msg(&log_tx, &format!("  ❌ {pkg} - not installed ({reason})\n"));
+                missing.push((pkg, reason));
+            }
         }
     }
 
     // ── Offer to install missing packages ─────────────────────────────────────
     if !missing.is_empty() {
+        let names: Vec<&str> = missing.iter().map(|(pkg, _)| *pkg).collect();
         log_msg(
             &log_tx,
-            &format!("\n⚠️  Missing Homebrew packages: {}\n", missing.join(", ")),
+            &format!("\n⚠️  Missing Homebrew packages: {}\n", names.join(", ")),
         );
 
         let count = missing.len();
-        let preview = missing
-            .iter()
-            .take(5)
-            .cloned()
-            .collect::<Vec<_>>()
-            .join(", ");
-        let extra = if count > 5 {
-            format!(", and {} more", count - 5)
-        } else {
-            String::new()
-        };
-
         let message = format!(
-            "Found {count} missing package{}:\n\n{preview}{extra}\n\nInstall all missing packages now?",
+            "Found {count} missing package{}. Untick any you manage yourself, then install the rest:",
             if count == 1 { "" } else { "s" }
         );
+        let items = missing
+            .iter()
+            .map(|(pkg, reason)| ConfirmItem {
+                label: (*pkg).to_owned(),
+                detail: (*reason).to_owned(),
+                checked: true,
+            })
+            .collect();
 
-        let should_install =
-            ask_confirm(&confirm_tx, "Install Missing Dependencies", &message).await;
-
-        if should_install {
-            for pkg in &missing {
-                log_msg(&log_tx, &format!("\n📦 Installing {pkg}...\n"));
-                // Pass brew path and pkg as separate shell words; neither
-                // should contain spaces but quoting makes it explicit.
-                let cmd = format!("{brew:?} install {pkg}");
-                match run_command(&cmd, None, &env, &log_tx).await {
-                    Ok(()) => log_msg(&log_tx, &format!("✓ {pkg} installed successfully\n")),
-                    Err(e) => {
-                        log_msg(&log_tx, &format!("❌ Failed to install {pkg}: {e}\n"));
-                        log_tx
-                            .send(AppMessage::ShowDialog {
-                                title: "Installation Failed".into(),
-                                message: format!("Failed to install {pkg}:\n{e}"),
-                                is_error: true,
-                            })
-                            .ok();
-                    }
-                }
-            }
-        } else {
+        let chosen = ask_checklist(&confirm_tx, "Install Missing Dependencies", &message, items)
+            .await
+            .map(|ticked| {
+                names
+                    .iter()
+                    .zip(ticked)
+                    .filter_map(|(pkg, keep)| keep.then_some(*pkg))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        let skipped: Vec<&str> = names
+            .iter()
+            .filter(|pkg| !chosen.contains(pkg))
+            .copied()
+            .collect();
+
+        if !chosen.is_empty() {
+            installed.extend(install_packages(&brew, &chosen, &env, &log_tx).await?);
+        }
+        if !skipped.is_empty() {
             log_msg(
                 &log_tx,
-                "\n⚠️  Dependencies not installed. Compilation may fail.\n",
+                &format!(
+                    "\n⚠️  Not installed: {}. Compilation may fail.\n",
+                    skipped.join(", ")
+                ),
             );
         }
     } else {
         log_msg(&log_tx, "\n✓ All Homebrew packages are installed!\n");
     }
+    // The complete set, so the UI can drop packages removed since last time.
+    log_tx
+        .send(AppMessage::InstalledPackages(
+            installed.into_iter().collect(),
+        ))
+        .ok();
 
     // ── Check Rust toolchain ──────────────────────────────────────────────────
-    let rust_ok = check_rust_installation(&brew, &env, &log_tx).await;
+    let rust_ok = if selection.target.includes_electrs() {
+        check_rust_installation(&brew, &selection.options.rust_toolchain, &env, &log_tx).await
+    } else {
+        true
+    };
+
+    // ── Check tool versions ───────────────────────────────────────────────────
+    let tools_ok = check_tool_versions(&brew, &selection, &env, &log_tx, &confirm_tx).await;
 
     log_msg(&log_tx, "\n=== Dependency Check Complete ===\n");
 
-    if rust_ok {
-        log_msg(&log_tx, "\n✓ Rust toolchain is ready!\n");
+    if rust_ok && tools_ok {
+        if selection.target.includes_electrs() {
+            log_msg(&log_tx, "\n✓ Rust toolchain is ready!\n");
+        }
         log_tx
             .send(AppMessage::ShowDialog {
                 title:    "Dependency Check".into(),
@@ -141,7 +702,11 @@ pub async fn check_dependencies_task(
     } else {
         log_msg(
             &log_tx,
-            "\n⚠️  Rust toolchain needs attention (see messages above)\n",
+            if rust_ok {
+                "\n⚠️  Some tools need attention (see messages above)\n"
+            } else {
+                "\n⚠️  Rust toolchain needs attention (see messages above)\n"
+            },
         );
         log_tx
             .send(AppMessage::ShowDialog {
@@ -152,18 +717,27 @@ pub async fn check_dependencies_task(
             .ok();
     }
 
-    Ok(rust_ok)
+    Ok(rust_ok && tools_ok)
 }
 
 // ─── Rust toolchain check ─────────────────────────────────────────────────────
 
+/// Verify rustc and cargo, preferring rustup over Homebrew's `rust` formula
+/// when both are possible, and install a pinned toolchain on demand.
 async fn check_rust_installation(
     brew: &str,
+    pinned: &str,
     env: &HashMap<String, String>,
     log_tx: &Sender<AppMessage>,
 ) -> bool {
     log_msg(log_tx, "\n=== Checking Rust Toolchain ===\n");
 
+    let rustup = rustup_version(env).await;
+    match &rustup {
+        Some(v) => log_msg(log_tx, &format!("✓ rustup found: {v}\n")),
+        None => log_msg(log_tx, "ℹ️  rustup not found — toolchain pinning is unavailable\n"),
+    }
+
     let rustc_ok = if let Some(v) = probe(&["rustc", "--version"], env).await {
         log_msg(log_tx, &format!("✓ rustc found: {v}\n"));
         true
@@ -181,39 +755,53 @@ async fn check_rust_installation(
     };
 
     if rustc_ok && cargo_ok {
-        return true;
+        return check_pinned_toolchain(pinned, rustup.is_some(), env, log_tx).await;
     }
 
-    // ── Try installing via Homebrew ───────────────────────────────────────────
     log_msg(log_tx, "\n❌ Rust toolchain not found or incomplete!\n");
-    log_msg(log_tx, "Installing Rust via Homebrew...\n");
-
-    // Non-blocking check that brew knows the rust formula.
-    let brew_knows_rust = tokio::process::Command::new(brew)
-        .args(["info", "rust"])
-        .env_clear()
-        .envs(env)
-        .output()
-        .await
-        .map(|o| o.status.success())
-        .unwrap_or(false);
 
-    if !brew_knows_rust {
-        log_msg(log_tx, "❌ Rust formula not found in Homebrew\n");
-        log_tx
-            .send(AppMessage::ShowDialog {
-                title:    "Rust Installation Failed".into(),
-                message:  "Could not install Rust via Homebrew.\n\nPlease install manually:\n1. Visit https://rustup.rs\n2. Run: curl --proto '=https' --tlsv1.2 -sSf https://sh.rustup.rs | sh\n3. Restart this app".into(),
-                is_error: true,
-            })
-            .ok();
-        return false;
-    }
+    if rustup.is_some() {
+        // ── rustup is present but has no default toolchain ───────────────────
+        log_msg(log_tx, "Installing the stable toolchain via rustup...\n");
+        let cmd = CommandSpec::new("rustup", env).args(["default", "stable"]);
+        if let Err(e) = run_command(&cmd, log_tx).await {
+            log_msg(log_tx, &format!("❌ Failed to install Rust: {e}\n"));
+            log_tx
+                .send(AppMessage::ShowDialog {
+                    title:    "Installation Error".into(),
+                    message:  format!("rustup could not install the stable toolchain:\n{e}"),
+                    is_error: true,
+                })
+                .ok();
+            return false;
+        }
+    } else {
+        // ── Fall back to Homebrew ─────────────────────────────────────────────
+        log_msg(log_tx, "Installing Rust via Homebrew...\n");
 
-    log_msg(log_tx, "📦 Installing rust from Homebrew...\n");
-    let brew_cmd = format!("{brew:?} install rust");
-    match run_command(&brew_cmd, None, env, log_tx).await {
-        Err(e) => {
+        // Non-blocking check that brew knows the rust formula.
+        let info = CommandSpec::new(brew, env)
+            .args(["info", "rust"])
+            .timeout(BREW_QUERY_TIMEOUT);
+        let brew_knows_rust = capture(&info, log_tx)
+            .await
+            .is_ok_and(|o| o.status.success());
+
+        if !brew_knows_rust {
+            log_msg(log_tx, "❌ Rust formula not found in Homebrew\n");
+            log_tx
+                .send(AppMessage::ShowDialog {
+                    title:    "Rust Installation Failed".into(),
+                    message:  "Could not install Rust via Homebrew.\n\nPlease install manually:\n1. Visit https://rustup.rs\n2. Run: curl --proto '=https' --tlsv1.2 -sSf https://sh.rustup.rs | sh\n3. Restart this app".into(),
+                    is_error: true,
+                })
+                .ok();
+            return false;
+        }
+
+        log_msg(log_tx, "📦 Installing rust from Homebrew...\n");
+        let brew_cmd = CommandSpec::new(brew, env).args(["install", "rust"]);
+        if let Err(e) = run_command(&brew_cmd, log_tx).await {
             log_msg(log_tx, &format!("❌ Failed to install Rust: {e}\n"));
             log_tx
                 .send(AppMessage::ShowDialog {
@@ -224,12 +812,11 @@ async fn check_rust_installation(
                 .ok();
             return false;
         }
-        Ok(()) => {
-            log_msg(log_tx, "\nVerifying Rust installation...\n");
-            tokio::time::sleep(std::time::Duration::from_secs(2)).await;
-        }
     }
 
+    log_msg(log_tx, "\nVerifying Rust installation...\n");
+    tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+
     // Re-check after installation.
     match (
         probe(&["rustc", "--version"], env).await,
@@ -238,7 +825,7 @@ async fn check_rust_installation(
         (Some(r), Some(c)) => {
             log_msg(log_tx, &format!("✓ rustc installed: {r}\n"));
             log_msg(log_tx, &format!("✓ cargo installed: {c}\n"));
-            true
+            check_pinned_toolchain(pinned, rustup.is_some(), env, log_tx).await
         }
         _ => {
             log_msg(
@@ -257,16 +844,177 @@ async fn check_rust_installation(
     }
 }
 
+/// Make sure a pinned toolchain (if any) is installed.  Pinning needs rustup.
+async fn check_pinned_toolchain(
+    pinned: &str,
+    have_rustup: bool,
+    env: &HashMap<String, String>,
+    log_tx: &Sender<AppMessage>,
+) -> bool {
+    let pinned = pinned.trim();
+    if pinned.is_empty() {
+        return true;
+    }
+    if !have_rustup {
+        log_msg(
+            log_tx,
+            &format!("❌ Toolchain {pinned} is pinned but rustup is not installed (https://rustup.rs)\n"),
+        );
+        return false;
+    }
+    match ensure_toolchain(pinned, env, log_tx).await {
+        Ok(()) => true,
+        Err(e) => {
+            log_msg(log_tx, &format!("❌ {e:#}\n"));
+            false
+        }
+    }
+}
+
 // ─── Confirmation helper ──────────────────────────────────────────────────────
 
 /// Send a `ConfirmRequest` to the UI, then suspend until the UI replies.
 async fn ask_confirm(tx: &Sender<ConfirmRequest>, title: &str, message: &str) -> bool {
-    let (response_tx, response_rx) = oneshot::channel::<bool>();
+    ask_checklist(tx, title, message, Vec::new()).await.is_some()
+}
+
+/// Like `ask_confirm`, but with a checklist.  Returns one flag per item, or
+/// `None` when the user cancelled.
+async fn ask_checklist(
+    tx: &Sender<ConfirmRequest>,
+    title: &str,
+    message: &str,
+    items: Vec<ConfirmItem>,
+) -> Option<Vec<bool>> {
+    let (response_tx, response_rx) = oneshot::channel::<Option<Vec<bool>>>();
     tx.send(ConfirmRequest {
         title: title.to_owned(),
         message: message.to_owned(),
+        items,
         response_tx,
     })
     .ok();
-    response_rx.await.unwrap_or(false)
+    response_rx.await.unwrap_or(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::Target;
+
+    fn selection(target: Target, wallet: bool, zmq: bool) -> BuildSelection {
+        BuildSelection {
+            target,
+            bitcoin_version: "v29.0".to_owned(),
+            electrs_version: "v0.10.9".to_owned(),
+            options: BuildOptions {
+                wallet,
+                zmq,
+                rust_toolchain: String::new(),
+            },
+        }
+    }
+
+    fn formulae(selection: &BuildSelection) -> Vec<&'static str> {
+        required_packages(selection)
+            .into_iter()
+            .map(|(formula, _)| formula)
+            .collect()
+    }
+
+    #[test]
+    fn required_packages_follow_target_and_options() {
+        const BITCOIN: &[&str] = &["git", "cmake", "pkgconf", "boost", "libevent"];
+        let cases: &[(Target, bool, bool, &[&str])] = &[
+            (Target::Bitcoin, false, false, BITCOIN),
+            (
+                Target::Bitcoin,
+                true,
+                false,
+                &["git", "cmake", "pkgconf", "boost", "libevent", "sqlite"],
+            ),
+            (
+                Target::Bitcoin,
+                false,
+                true,
+                &["git", "cmake", "pkgconf", "boost", "libevent", "zeromq"],
+            ),
+            (
+                Target::Bitcoin,
+                true,
+                true,
+                &[
+                    "git", "cmake", "pkgconf", "boost", "libevent", "sqlite", "zeromq",
+                ],
+            ),
+            // Bitcoin Core's options do not apply to an Electrs-only build.
+            (Target::Electrs, false, false, &["git", "llvm"]),
+            (Target::Electrs, true, true, &["git", "llvm"]),
+            (
+                Target::Both,
+                false,
+                false,
+                &["git", "cmake", "pkgconf", "boost", "libevent", "llvm"],
+            ),
+            (
+                Target::Both,
+                true,
+                true,
+                &[
+                    "git", "cmake", "pkgconf", "boost", "libevent", "sqlite", "zeromq", "llvm",
+                ],
+            ),
+        ];
+        for &(target, wallet, zmq, expected) in cases {
+            assert_eq!(
+                formulae(&selection(target, wallet, zmq)),
+                expected,
+                "{target:?} wallet={wallet} zmq={zmq}"
+            );
+        }
+    }
+
+    #[test]
+    fn required_packages_have_reasons() {
+        for (formula, reason) in required_packages(&selection(Target::Both, true, true)) {
+            assert!(!reason.is_empty(), "{formula} has no reason");
+        }
+    }
+
+    #[test]
+    fn brew_versions_are_parsed() {
+        let output = "boost 1.86.0\n\
+                      openssl@3 3.2.1 3.3.0\n\
+                      python@3.12 3.12.7_1\n\
+                      llvm@18 18.1.8\n";
+        let versions = parse_brew_versions(output);
+        assert_eq!(versions.len(), 4);
+        assert_eq!(versions["boost"], "1.86.0");
+        assert_eq!(versions["openssl@3"], "3.3.0");
+        assert_eq!(versions["python@3.12"], "3.12.7_1");
+        assert_eq!(versions["llvm@18"], "18.1.8");
+    }
+
+    #[test]
+    fn brew_versions_skip_empty_and_bare_lines() {
+        assert!(parse_brew_versions("").is_empty());
+        assert!(parse_brew_versions("\n  \n").is_empty());
+        let versions = parse_brew_versions("cmake\r\ngit 2.47.0\r\n");
+        assert_eq!(versions.len(), 1);
+        assert_eq!(versions["git"], "2.47.0");
+    }
+
+    #[test]
+    fn version_range_bounds() {
+        let since = Some((29, 0, 0));
+        let until = Some((30, 0, 0));
+        assert!(version_in_range("v29.0", since, until));
+        assert!(version_in_range("v29.1", since, until));
+        assert!(!version_in_range("v28.1", since, until));
+        assert!(!version_in_range("v30.0", since, until));
+        assert!(version_in_range("v28.1", None, until));
+        // A tag still loading counts as the newest release.
+        assert!(version_in_range("Loading…", since, None));
+        assert!(!version_in_range("Loading…", None, until));
+    }
 }
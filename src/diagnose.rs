@@ -0,0 +1,350 @@
+// src/diagnose.rs
+//
+// Known-failure diagnosis: when a build fails, look through the error and
+// the tail of the failed command's output (`CommandError`) for signatures
+// of problems we have seen before, and say what went wrong, how to fix it
+// and — where BitForge can do it — a one-click `Remedy`.
+
+use crate::ansi;
+use crate::process::CommandError;
+
+// ─── Rules ────────────────────────────────────────────────────────────────────
+
+/// One known failure and the output that gives it away.
+struct Rule {
+    title: &'static str,
+    /// Lowercase substrings, any one of which identifies the failure.
+    signatures: &'static [&'static str],
+    /// What went wrong, in a sentence or two.
+    explanation: &'static str,
+    /// What the user should do, shown whether or not there is a remedy.
+    fix: &'static str,
+    remedy: Option<Remedy>,
+}
+
+/// Longer evidence lines are cut to this many characters.
+const MAX_EVIDENCE: usize = 200;
+
+/// Checked in order; the first rule with a matching line wins.  Rules whose
+/// cause would also produce another rule's symptoms come first — an
+/// unaccepted Xcode license breaks every compiler check, so it must win
+/// over "pkg-config not found".
+const RULES: &[Rule] = &[
+    Rule {
+        title: "Xcode license not accepted",
+        signatures: &[
+            "agreeing to the xcode/ios license",
+            "you have not agreed to the xcode license",
+            "xcodebuild -license",
+        ],
+        explanation: "Apple's compilers refuse to run until the Xcode license has been accepted.",
+        fix: "Run `sudo xcodebuild -license accept` in Terminal, then build again.",
+        remedy: None,
+    },
+    Rule {
+        title: "Command Line Tools missing",
+        signatures: &[
+            "xcrun: error: invalid active developer path",
+            "xcrun: error: unable to find utility",
+        ],
+        explanation: "The Xcode Command Line Tools, which provide the compilers, are not installed — often after a macOS upgrade.",
+        fix: "Run `xcode-select --install`, finish the installer, then build again.",
+        remedy: Some(Remedy::Run("xcode-select", &["--install"])),
+    },
+    Rule {
+        title: "Disk full",
+        signatures: &[
+            "no space left on device",
+            "disk quota exceeded",
+            "not enough space on the disk",
+        ],
+        explanation: "The build ran out of disk space.  A full build needs several gigabytes in the build directory.",
+        fix: "Free some space, or choose a build directory on a larger disk, then build again.",
+        remedy: None,
+    },
+    Rule {
+        title: "Compiler ran out of memory",
+        signatures: &[
+            "signal terminated program cc1plus",
+            "killed signal terminated program",
+            "clang frontend command failed due to signal",
+            "unable to execute command: killed",
+            "virtual memory exhausted",
+            "cannot allocate memory",
+            "sigkill: kill",
+        ],
+        explanation: "A compiler process was killed, almost always because parallel jobs used more memory than the machine has.",
+        fix: "Build again with fewer parallel jobs.",
+        remedy: Some(Remedy::FewerJobs),
+    },
+    Rule {
+        title: "CMake too old",
+        signatures: &["you are running version"],
+        explanation: "The installed CMake is older than this version of Bitcoin Core requires.",
+        fix: "Run `brew upgrade cmake`, then build again.",
+        remedy: Some(Remedy::Brew(&["upgrade", "cmake"])),
+    },
+    Rule {
+        title: "CMake not installed",
+        signatures: &["failed to spawn: cmake"],
+        explanation: "Bitcoin Core v29 and later build with CMake, which could not be found.",
+        fix: "Run `brew install cmake`, then build again.",
+        remedy: Some(Remedy::Brew(&["install", "cmake"])),
+    },
+    Rule {
+        title: "libclang not found",
+        signatures: &[
+            "unable to find libclang",
+            "couldn't find any valid shared libraries matching: ['libclang",
+        ],
+        explanation: "Electrs generates its RocksDB bindings with bindgen, which needs libclang from LLVM.",
+        fix: "Run `brew install llvm`, then build again.",
+        remedy: Some(Remedy::Brew(&["install", "llvm"])),
+    },
+    Rule {
+        title: "pkg-config not found",
+        signatures: &[
+            "could not find pkgconfig",
+            "pkg-config: command not found",
+            "the pkg-config script could not be found",
+        ],
+        explanation: "The build locates libevent and other libraries through pkg-config, which is not installed.",
+        fix: "Run `brew install pkgconf`, then build again.",
+        remedy: Some(Remedy::Brew(&["install", "pkgconf"])),
+    },
+    Rule {
+        title: "libevent not found",
+        signatures: &[
+            "package 'libevent', required by",
+            "no package 'libevent' found",
+            "could not find libevent",
+            "libevent not found",
+        ],
+        explanation: "Bitcoin Core's networking and RPC server need libevent, which is not installed.",
+        fix: "Run `brew install libevent`, then build again.",
+        remedy: Some(Remedy::Brew(&["install", "libevent"])),
+    },
+    Rule {
+        title: "Secure connection to GitHub failed",
+        signatures: &[
+            "gnutls_handshake() failed",
+            "ssl certificate problem",
+            "server certificate verification failed",
+            "ssl_error_syscall",
+            "ssl_connect",
+        ],
+        explanation: "git could not set up a TLS connection — a dropped or filtered connection, a proxy, or out-of-date certificates.",
+        fix: "Check the network connection and any proxy, then build again.  If a certificate error persists, run `brew install ca-certificates`.",
+        remedy: None,
+    },
+];
+
+// ─── Diagnosis ────────────────────────────────────────────────────────────────
+
+/// Something BitForge can do about a failure on the user's say-so.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Remedy {
+    /// Run Homebrew with these arguments.
+    Brew(&'static [&'static str]),
+    /// Run this program with these arguments.
+    Run(&'static str, &'static [&'static str]),
+    /// Build again with half as many parallel jobs.
+    FewerJobs,
+}
+
+impl Remedy {
+    /// Button text, e.g. `Run brew install llvm`.
+    #[must_use]
+    pub fn label(self) -> String {
+        match self {
+            Remedy::Brew(args) => format!("Run brew {}", args.join(" ")),
+            Remedy::Run(program, args) => format!("Run {program} {}", args.join(" ")),
+            Remedy::FewerJobs => "Retry with fewer jobs".to_owned(),
+        }
+    }
+}
+
+/// A failure recognised by `diagnose`.
+#[derive(Clone, Debug)]
+pub struct Diagnosis {
+    pub title: &'static str,
+    pub explanation: &'static str,
+    pub fix: &'static str,
+    /// The line that matched, trimmed to `MAX_EVIDENCE` characters.
+    pub evidence: String,
+    pub remedy: Option<Remedy>,
+}
+
+impl Diagnosis {
+    /// The diagnosis as a few indented lines, for logs and text front-ends.
+    #[must_use]
+    pub fn report(&self) -> String {
+        format!(
+            "🩺 Likely cause: {}\n   {}\n   Fix: {}\n   Seen: {}\n",
+            self.title, self.explanation, self.fix, self.evidence
+        )
+    }
+}
+
+/// Match `err` — its whole context chain and, if a command failed, the end
+/// of that command's output — against the known failures.
+#[must_use]
+pub fn diagnose(err: &anyhow::Error) -> Option<Diagnosis> {
+    let output = err
+        .chain()
+        .find_map(|e| e.downcast_ref::<CommandError>())
+        .map(|e| ansi::strip(&e.output).into_owned())
+        .unwrap_or_default();
+    let chain = format!("{err:#}");
+    let lines: Vec<(&str, String)> = chain
+        .lines()
+        .chain(output.lines())
+        .map(|line| (line.trim(), line.to_lowercase()))
+        .collect();
+
+    RULES.iter().find_map(|rule| {
+        let (line, _) = lines.iter().find(|(_, lower)| {
+            rule.signatures
+                .iter()
+                .any(|signature| lower.contains(signature))
+        })?;
+        Some(Diagnosis {
+            title: rule.title,
+            explanation: rule.explanation,
+            fix: rule.fix,
+            evidence: line.chars().take(MAX_EVIDENCE).collect(),
+            remedy: rule.remedy,
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A failed command as `run_command` reports it, with `output` as what
+    /// it printed.
+    fn command_failure(output: &str) -> anyhow::Error {
+        anyhow::Error::new(CommandError {
+            message: "Command failed (exit 2): cmake --build build".to_owned(),
+            output: output.to_owned(),
+        })
+        .context("cmake build failed")
+    }
+
+    fn title(err: &anyhow::Error) -> Option<&'static str> {
+        diagnose(err).map(|d| d.title)
+    }
+
+    #[test]
+    fn each_rule_matches_real_output() {
+        let cases: &[(&str, &str)] = &[
+            (
+                "Error: You have not agreed to the Xcode license agreements. \
+                 Please run 'sudo xcodebuild -license' from within a Terminal window",
+                "Xcode license not accepted",
+            ),
+            (
+                "xcrun: error: invalid active developer path (/Library/Developer/CommandLineTools), \
+                 missing xcrun at: /Library/Developer/CommandLineTools/usr/bin/xcrun",
+                "Command Line Tools missing",
+            ),
+            (
+                "/usr/bin/ld: final link failed: No space left on device",
+                "Disk full",
+            ),
+            (
+                "c++: fatal error: Killed signal terminated program cc1plus\n\
+                 compilation terminated.",
+                "Compiler ran out of memory",
+            ),
+            (
+                "CMake Error at CMakeLists.txt:7 (cmake_minimum_required):\n  \
+                 CMake 3.22 or higher is required.  You are running version 3.16.3",
+                "CMake too old",
+            ),
+            (
+                "thread 'main' panicked at bindgen: Unable to find libclang: \
+                 \"couldn't find any valid shared libraries matching: ['libclang.dylib']\"",
+                "libclang not found",
+            ),
+            (
+                "CMake Error: Could NOT find PkgConfig (missing: PKG_CONFIG_EXECUTABLE)",
+                "pkg-config not found",
+            ),
+            (
+                "-- Checking for module 'libevent>=2.1.8'\n\
+                 --   No package 'libevent' found",
+                "libevent not found",
+            ),
+            (
+                "fatal: unable to access 'https://github.com/bitcoin/bitcoin.git/': \
+                 gnutls_handshake() failed: The TLS connection was non-properly terminated.",
+                "Secure connection to GitHub failed",
+            ),
+        ];
+        for (output, expected) in cases {
+            assert_eq!(title(&command_failure(output)), Some(*expected), "{output}");
+        }
+    }
+
+    #[test]
+    fn context_chain_is_searched() {
+        // Spawning fails before there is any output to search.
+        let err =
+            anyhow::anyhow!("Failed to spawn: cmake -B build").context("cmake configure failed");
+        assert_eq!(title(&err), Some("CMake not installed"));
+    }
+
+    #[test]
+    fn earlier_rules_win() {
+        let cases: &[(&str, &str)] = &[
+            // An unaccepted license breaks every compiler check cmake runs.
+            (
+                "You have not agreed to the Xcode license agreements.\n\
+                 CMake Error: Could NOT find PkgConfig (missing: PKG_CONFIG_EXECUTABLE)",
+                "Xcode license not accepted",
+            ),
+            // A full disk can take a compiler down with it.
+            (
+                "c++: fatal error: Killed signal terminated program cc1plus\n\
+                 /tmp/ccXyZ.s: No space left on device",
+                "Disk full",
+            ),
+            // Missing pkg-config explains the missing libevent.
+            (
+                "-- Could NOT find PkgConfig\n\
+                 CMake Error: libevent not found",
+                "pkg-config not found",
+            ),
+        ];
+        for (output, expected) in cases {
+            assert_eq!(title(&command_failure(output)), Some(*expected), "{output}");
+        }
+    }
+
+    #[test]
+    fn evidence_is_the_matching_line() {
+        let output = "[ 41%] Building CXX object src/CMakeFiles/bitcoin_node.dir/init.cpp.o\n\
+                      \x1b[1;31m  c++: fatal error: Killed signal terminated program cc1plus\x1b[0m\n\
+                      gmake[2]: *** [src/CMakeFiles/bitcoin_node.dir/build.make:76] Error 1\n";
+        let diagnosis = diagnose(&command_failure(output)).expect("diagnosed");
+        assert_eq!(
+            diagnosis.evidence,
+            "c++: fatal error: Killed signal terminated program cc1plus"
+        );
+        assert_eq!(diagnosis.remedy, Some(Remedy::FewerJobs));
+
+        let long = format!("No space left on device {}", "x".repeat(500));
+        let diagnosis = diagnose(&command_failure(&long)).expect("diagnosed");
+        assert_eq!(diagnosis.evidence.chars().count(), MAX_EVIDENCE);
+    }
+
+    #[test]
+    fn unknown_failures_are_not_diagnosed() {
+        let output = "src/init.cpp:12:10: error: expected ';' after expression\n\
+                      gmake: *** [all] Error 2";
+        assert_eq!(title(&command_failure(output)), None);
+        assert_eq!(title(&anyhow::anyhow!("GitHub API returned 404")), None);
+    }
+}
@@ -3,36 +3,104 @@
 // Homebrew discovery and build environment construction.
 
 use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+
+use anyhow::{bail, Result};
+
+use crate::process::probe;
 
 // ─── Homebrew discovery ───────────────────────────────────────────────────────
 
-/// Return the path to the `brew` binary, checking Apple Silicon first.
-#[must_use]
-pub fn find_brew() -> Option<String> {
-    const CANDIDATES: [&str; 2] = ["/opt/homebrew/bin/brew", "/usr/local/bin/brew"];
-    CANDIDATES
-        .iter()
-        .copied()
-        .find(|p| std::path::Path::new(p).is_file())
-        .map(str::to_owned)
+/// Environment variable that points BitForge at a specific Homebrew install.
+/// May name either the `brew` binary or the prefix directory containing it,
+/// and takes precedence over the `brew_path` setting.
+pub const BREW_OVERRIDE_VAR: &str = "BITFORGE_BREW";
+
+/// Return the path to the `brew` binary.
+///
+/// Search order: the user-specified `custom` location (binary or prefix),
+/// Apple Silicon, Intel macOS, system-wide Linuxbrew, per-user Linuxbrew,
+/// and finally any `brew` on `PATH`.  A `custom` location without a brew
+/// binary is an error rather than a reason to fall back to another install.
+pub fn find_brew(custom: Option<&str>) -> Result<Option<String>> {
+    if let Some(custom) = custom.map(str::trim).filter(|s| !s.is_empty()) {
+        let path = Path::new(custom);
+        if path.is_file() {
+            return Ok(Some(custom.to_owned()));
+        }
+        let nested = path.join("bin").join("brew");
+        if nested.is_file() {
+            return Ok(Some(nested.to_string_lossy().into_owned()));
+        }
+        bail!("Homebrew location {custom:?} is neither a brew binary nor a prefix containing bin/brew");
+    }
+
+    let mut candidates: Vec<PathBuf> = vec![
+        PathBuf::from("/opt/homebrew/bin/brew"),
+        PathBuf::from("/usr/local/bin/brew"),
+        PathBuf::from("/home/linuxbrew/.linuxbrew/bin/brew"),
+    ];
+    if let Some(home) = std::env::var_os("HOME") {
+        candidates.push(PathBuf::from(home).join(".linuxbrew/bin/brew"));
+    }
+    if let Some(path) = std::env::var_os("PATH") {
+        candidates.extend(std::env::split_paths(&path).map(|dir| dir.join("brew")));
+    }
+
+    Ok(candidates
+        .into_iter()
+        .find(|p| p.is_file())
+        .map(|p| p.to_string_lossy().into_owned()))
 }
 
-/// Derive the Homebrew prefix from the brew binary path.
+/// Return the Homebrew location the user asked for: [`BREW_OVERRIDE_VAR`]
+/// if set, else `saved` (the `brew_path` setting).
 #[must_use]
-pub fn brew_prefix(brew: &str) -> String {
-    if brew.contains("/opt/homebrew") {
-        "/opt/homebrew".to_owned()
-    } else {
-        "/usr/local".to_owned()
+pub fn brew_override(saved: Option<&str>) -> Option<String> {
+    std::env::var(BREW_OVERRIDE_VAR)
+        .ok()
+        .filter(|s| !s.trim().is_empty())
+        .or_else(|| saved.filter(|s| !s.trim().is_empty()).map(str::to_owned))
+}
+
+/// Ask the brew binary for its prefix (`brew --prefix`), once per binary.
+///
+/// Falls back to the directory above `bin/` when brew cannot be run or
+/// does not answer in time, which is correct for every standard install
+/// layout; `None` when `brew` does not live in a `bin/` directory either.
+pub async fn brew_prefix(brew: &str) -> Option<String> {
+    static PREFIXES: LazyLock<Mutex<HashMap<String, Option<String>>>> =
+        LazyLock::new(Mutex::default);
+    let known = PREFIXES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(brew)
+        .cloned();
+    if let Some(prefix) = known {
+        return prefix;
     }
+    let env: HashMap<String, String> = std::env::vars().collect();
+    let prefix = probe(&[brew, "--prefix"], &env).await.or_else(|| {
+        let bin = Path::new(brew).parent()?;
+        if bin.file_name()? != "bin" {
+            return None;
+        }
+        bin.parent().map(|p| p.to_string_lossy().into_owned())
+    });
+    PREFIXES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(brew.to_owned(), prefix.clone());
+    prefix
 }
 
 // ─── Build environment ────────────────────────────────────────────────────────
 
 /// Build a complete process environment suitable for spawning compilation
-/// children.  Prepends Homebrew, Cargo, and LLVM paths to `PATH`, sets
-/// `LIBCLANG_PATH` / `DYLD_LIBRARY_PATH` for RocksDB bindgen, and inherits
-/// everything else from the parent process.
+/// children.  Prepends Homebrew, Cargo, and LLVM paths to `PATH`, exports
+/// `HOMEBREW_PREFIX`, sets `LIBCLANG_PATH` and the dynamic library path for
+/// RocksDB bindgen, and inherits everything else from the parent process.
 #[must_use]
 pub fn setup_build_environment(brew_pfx: Option<&str>) -> HashMap<String, String> {
     let mut env: HashMap<String, String> = std::env::vars().collect();
@@ -44,23 +112,32 @@ pub fn setup_build_environment(brew_pfx: Option<&str>) -> HashMap<String, String
         .to_owned();
 
     // ── Build ordered PATH components ────────────────────────────────────────
-    // Capacity estimate: prefix bin + 2 homebrew locations + cargo + llvm +
-    // existing PATH split + 4 system dirs.
+    // Capacity estimate: prefix bin + sbin + cargo + llvm + existing PATH
+    // split + 4 system dirs.
     let mut path_parts: Vec<&str> = Vec::with_capacity(16);
 
     // Declare owned strings that need to live long enough.
     let pfx_bin;
+    let pfx_sbin;
     let llvm_bin_owned;
 
+    // rustup's proxies must come before Homebrew, otherwise a brew-installed
+    // `rust` formula shadows them and toolchain selection silently fails.
+    let cargo_bin = format!("{home}/.cargo/bin");
+    let has_cargo_bin = Path::new(&cargo_bin).is_dir();
+    let rustup_managed = Path::new(&cargo_bin).join("rustup").is_file();
+    if rustup_managed {
+        path_parts.push(&cargo_bin);
+    }
+
     if let Some(pfx) = brew_pfx {
         pfx_bin = format!("{pfx}/bin");
+        pfx_sbin = format!("{pfx}/sbin");
         path_parts.push(&pfx_bin);
+        path_parts.push(&pfx_sbin);
     }
-    path_parts.push("/opt/homebrew/bin");
-    path_parts.push("/usr/local/bin");
 
-    let cargo_bin = format!("{home}/.cargo/bin");
-    if std::path::Path::new(&cargo_bin).is_dir() {
+    if has_cargo_bin && !rustup_managed {
         path_parts.push(&cargo_bin);
     }
 
@@ -73,7 +150,7 @@ pub fn setup_build_environment(brew_pfx: Option<&str>) -> HashMap<String, String
         llvm_bin_buf.clear();
         llvm_bin_buf.push_str(candidate);
         llvm_bin_buf.push_str("/bin");
-        if std::path::Path::new(&llvm_bin_buf).is_dir() {
+        if Path::new(&llvm_bin_buf).is_dir() {
             // Keep the bin path we found; derive lib path from it later.
             llvm_bin_owned = llvm_bin_buf.clone();
             path_parts.push(&llvm_bin_owned);
@@ -104,11 +181,17 @@ pub fn setup_build_environment(brew_pfx: Option<&str>) -> HashMap<String, String
 
     env.insert("PATH".to_owned(), deduped.join(":"));
 
+    // Child tools (and `bitcoin_env`'s pkg-config search path) key off this
+    // exactly as they would after `eval "$(brew shellenv)"`.
+    if let Some(pfx) = brew_pfx {
+        env.insert("HOMEBREW_PREFIX".to_owned(), pfx.to_owned());
+    }
+
     // ── LLVM library paths ────────────────────────────────────────────────────
     if let Some(pfx) = llvm_prefix_found {
         let lib = format!("{pfx}/lib");
         env.insert("LIBCLANG_PATH".to_owned(), lib.clone());
-        env.insert("DYLD_LIBRARY_PATH".to_owned(), lib);
+        env.insert(DYLIB_PATH_VAR.to_owned(), lib);
     }
 
     env
@@ -116,14 +199,18 @@ pub fn setup_build_environment(brew_pfx: Option<&str>) -> HashMap<String, String
 
 // ─── LLVM prefix candidates ───────────────────────────────────────────────────
 
+/// Dynamic loader search variable for the host platform.
+#[cfg(target_os = "macos")]
+const DYLIB_PATH_VAR: &str = "DYLD_LIBRARY_PATH";
+#[cfg(not(target_os = "macos"))]
+const DYLIB_PATH_VAR: &str = "LD_LIBRARY_PATH";
+
+/// Homebrew keeps LLVM keg-only under `<prefix>/opt/llvm` on every platform,
+/// so the discovered prefix is the only place worth looking.
 fn build_llvm_candidates(brew_pfx: Option<&str>) -> Vec<String> {
-    let mut v = Vec::with_capacity(3);
-    if let Some(pfx) = brew_pfx {
-        v.push(format!("{pfx}/opt/llvm"));
-    }
-    v.push("/opt/homebrew/opt/llvm".to_owned());
-    v.push("/usr/local/opt/llvm".to_owned());
-    v
+    brew_pfx
+        .map(|pfx| vec![format!("{pfx}/opt/llvm")])
+        .unwrap_or_default()
 }
 
 // ─── macOS version ────────────────────────────────────────────────────────────
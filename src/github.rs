@@ -3,12 +3,21 @@
 // Fetches latest stable release tags from the GitHub Releases API.
 // Versions are sorted newest-first by semver (major.minor.patch) so that
 // index 0 is always the most recent stable release, regardless of the
-// order GitHub returns them in.
+// order GitHub returns them in.  Transient failures (timeouts, 5xx) are
+// retried; see `retry`.  Running out of the unauthenticated rate limit is
+// reported with its reset time instead.
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
+use reqwest::StatusCode;
 use serde::Deserialize;
+use std::sync::mpsc::Sender;
 use std::sync::LazyLock;
 
+use crate::compiler::MIN_BITCOIN_VERSION;
+use crate::history::format_timestamp;
+use crate::messages::AppMessage;
+use crate::retry::{retry, transient_http};
+
 const BITCOIN_API: &str = "https://api.github.com/repos/bitcoin/bitcoin/releases?per_page=30";
 const ELECTRS_API: &str = "https://api.github.com/repos/romanz/electrs/releases?per_page=30";
 const MAX_VERSIONS: usize = 10;
@@ -38,29 +47,33 @@ struct GitHubRelease {
 
 // ─── Public fetch functions ───────────────────────────────────────────────────
 
-/// Fetch up to 10 stable Bitcoin Core release tags, newest first.
-pub async fn fetch_bitcoin_versions() -> Result<Vec<String>> {
-    fetch_versions(BITCOIN_API, "Bitcoin Core").await
+/// Fetch up to 10 stable Bitcoin Core release tags the build supports
+/// (`MIN_BITCOIN_VERSION` and later), newest first.  Retries are logged to
+/// `log_tx`.
+pub async fn fetch_bitcoin_versions(log_tx: &Sender<AppMessage>) -> Result<Vec<String>> {
+    let mut versions = fetch_versions(BITCOIN_API, "Bitcoin Core", log_tx).await?;
+    versions.retain(|v| parse_semver(v) >= MIN_BITCOIN_VERSION);
+    Ok(versions)
 }
 
-/// Fetch up to 10 stable Electrs release tags, newest first.
-pub async fn fetch_electrs_versions() -> Result<Vec<String>> {
-    fetch_versions(ELECTRS_API, "Electrs").await
+/// Fetch up to 10 stable Electrs release tags, newest first.  Retries are
+/// logged to `log_tx`.
+pub async fn fetch_electrs_versions(log_tx: &Sender<AppMessage>) -> Result<Vec<String>> {
+    fetch_versions(ELECTRS_API, "Electrs", log_tx).await
 }
 
 // ─── Shared implementation ────────────────────────────────────────────────────
 
-async fn fetch_versions(url: &str, project: &str) -> Result<Vec<String>> {
-    let releases: Vec<GitHubRelease> = HTTP_CLIENT
-        .get(url)
-        .send()
-        .await
-        .with_context(|| format!("HTTP GET failed for {project} releases"))?
-        .error_for_status()
-        .with_context(|| format!("GitHub API returned error status for {project}"))?
-        .json()
-        .await
-        .with_context(|| format!("Failed to parse {project} release JSON"))?;
+async fn fetch_versions(
+    url: &str,
+    project: &str,
+    log_tx: &Sender<AppMessage>,
+) -> Result<Vec<String>> {
+    let what = format!("Fetching {project} releases");
+    let releases = retry(&what, log_tx, transient_http, || {
+        fetch_releases(url, project)
+    })
+    .await?;
 
     let mut versions: Vec<String> = releases
         .into_iter()
@@ -78,11 +91,44 @@ async fn fetch_versions(url: &str, project: &str) -> Result<Vec<String>> {
     Ok(versions)
 }
 
+/// One request for `project`'s release list.
+async fn fetch_releases(url: &str, project: &str) -> Result<Vec<GitHubRelease>> {
+    let response = HTTP_CLIENT
+        .get(url)
+        .send()
+        .await
+        .with_context(|| format!("HTTP GET failed for {project} releases"))?;
+    // Not retried: the limit lasts until a reset up to an hour away.
+    if let Some(reset) = rate_limit_reset(&response) {
+        bail!("GitHub API rate limit reached while fetching {project} releases; it resets {reset}");
+    }
+    response
+        .error_for_status()
+        .with_context(|| format!("GitHub API returned error status for {project}"))?
+        .json()
+        .await
+        .with_context(|| format!("Failed to parse {project} release JSON"))
+}
+
+/// When GitHub's primary rate limit resets, e.g. `at 2026-01-02 15:04 UTC`,
+/// if `response` is that limit — a 403 with no requests remaining.
+fn rate_limit_reset(response: &reqwest::Response) -> Option<String> {
+    let header = |name: &str| response.headers().get(name).and_then(|v| v.to_str().ok());
+    if response.status() != StatusCode::FORBIDDEN || header("x-ratelimit-remaining") != Some("0") {
+        return None;
+    }
+    let reset = header("x-ratelimit-reset").and_then(|v| v.parse().ok());
+    Some(match reset {
+        Some(at) => format!("at {}", format_timestamp(at)),
+        None => "within the hour".to_owned(),
+    })
+}
+
 // ─── Semver parser ────────────────────────────────────────────────────────────
 
 /// Parse a version tag into a `(major, minor, patch)` tuple for sorting.
 /// Strips any leading `v`.  Unknown / malformed tags sort as `(0, 0, 0)`.
-fn parse_semver(tag: &str) -> (u32, u32, u32) {
+pub fn parse_semver(tag: &str) -> (u32, u32, u32) {
     let s = tag.trim_start_matches('v');
     let mut parts = s.splitn(4, '.').map(|p| p.parse::<u32>().unwrap_or(0));
     let major = parts.next().unwrap_or(0);
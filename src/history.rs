@@ -0,0 +1,281 @@
+// src/history.rs
+//
+// Persistent record of every build started from any front-end, stored as
+// JSON next to `settings.json`.  `compile_selection` appends one
+// `BuildRecord` per run; the GUI lists them in the Build History panel and
+// can restore any entry's settings to rebuild it.
+
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::options::{BuildOptions, Target};
+use crate::process::ResourceUsage;
+use crate::settings::config_dir;
+
+const HISTORY_FILE: &str = "history.json";
+/// Oldest entries are dropped beyond this many.
+const MAX_RECORDS: usize = 200;
+
+// ─── Record ───────────────────────────────────────────────────────────────────
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    Succeeded,
+    Failed,
+}
+
+impl Outcome {
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            Outcome::Succeeded => "Succeeded",
+            Outcome::Failed => "Failed",
+        }
+    }
+}
+
+/// One finished build.  Versions and commits are only set for the projects
+/// the target includes.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BuildRecord {
+    pub target: Target,
+    pub bitcoin_version: Option<String>,
+    pub bitcoin_commit: Option<String>,
+    pub electrs_version: Option<String>,
+    pub electrs_commit: Option<String>,
+    #[serde(default)]
+    pub options: BuildOptions,
+    pub cores: usize,
+    pub build_dir: PathBuf,
+    /// Seconds since the Unix epoch.
+    pub started_at: u64,
+    pub finished_at: u64,
+    /// Seconds the build spent paused, which its duration leaves out.
+    #[serde(default)]
+    pub paused_secs: u64,
+    pub outcome: Outcome,
+    /// First line of the error for failed builds.
+    #[serde(default)]
+    pub error: Option<String>,
+    #[serde(default)]
+    pub output_dirs: Vec<PathBuf>,
+    /// Full log of the run, when one was written.
+    #[serde(default)]
+    pub log_file: Option<PathBuf>,
+    /// CPU time and peak memory of all the build's commands, when it had
+    /// a log file to tally them with.
+    #[serde(default)]
+    pub usage: Option<ResourceUsage>,
+}
+
+impl BuildRecord {
+    /// e.g. `Bitcoin Core v29.0 + Electrs v0.10.9`.
+    #[must_use]
+    pub fn summary(&self) -> String {
+        let with_commit = |name: &str, version: &Option<String>, commit: &Option<String>| {
+            version.as_ref().map(|v| match commit {
+                Some(c) => format!("{name} {v} ({c})"),
+                None => format!("{name} {v}"),
+            })
+        };
+        [
+            with_commit("Bitcoin Core", &self.bitcoin_version, &self.bitcoin_commit),
+            with_commit("Electrs", &self.electrs_version, &self.electrs_commit),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" + ")
+    }
+
+    /// Enabled options, e.g. `wallet, zmq`; empty for a plain node build.
+    #[must_use]
+    pub fn options_summary(&self) -> String {
+        let mut parts = Vec::new();
+        if self.target.includes_bitcoin() {
+            if self.options.wallet {
+                parts.push("wallet".to_owned());
+            }
+            if self.options.zmq {
+                parts.push("zmq".to_owned());
+            }
+        }
+        if self.target.includes_electrs() && !self.options.rust_toolchain.is_empty() {
+            parts.push(format!("rust {}", self.options.rust_toolchain));
+        }
+        parts.join(", ")
+    }
+
+    /// Time spent building, not counting pauses.
+    #[must_use]
+    pub fn duration_secs(&self) -> u64 {
+        self.finished_at
+            .saturating_sub(self.started_at)
+            .saturating_sub(self.paused_secs)
+    }
+}
+
+// ─── Storage ──────────────────────────────────────────────────────────────────
+
+/// All recorded builds, oldest first.  A missing file is an empty history.
+pub fn load() -> Result<Vec<BuildRecord>> {
+    let Some(path) = history_path() else {
+        return Ok(Vec::new());
+    };
+    match read(&path)? {
+        Some(text) => parse(&text, &path),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Add `record` and rewrite the file atomically, keeping the newest
+/// `MAX_RECORDS`.
+///
+/// A file that cannot be read is left alone and nothing is recorded.  A
+/// malformed one — half-written, or from a newer BitForge — is moved aside
+/// to `history.json.bad-<stamp>` rather than overwritten, so no build is
+/// lost and future builds can still be recorded; the returned note says
+/// so, for the caller to pass on.
+pub fn append(record: BuildRecord) -> Result<Option<String>> {
+    let path = history_path().context("No home directory to store build history in")?;
+    let mut note = None;
+    let mut records = match read(&path)? {
+        None => Vec::new(),
+        Some(text) => match parse(&text, &path) {
+            Ok(records) => records,
+            Err(e) => {
+                let stamp = format_file_stamp(now_secs());
+                let bad = path.with_extension(format!("json.bad-{stamp}"));
+                std::fs::rename(&path, &bad).with_context(|| {
+                    format!("{e:#}; could not move it aside to {}", bad.display())
+                })?;
+                note = Some(format!(
+                    "{e:#}; moved it to {} and started a new history",
+                    bad.display()
+                ));
+                Vec::new()
+            }
+        },
+    };
+    records.push(record);
+    if records.len() > MAX_RECORDS {
+        records.drain(..records.len() - MAX_RECORDS);
+    }
+    write(&path, &records)?;
+    Ok(note)
+}
+
+/// The history file's text, or `None` if there is no file yet.
+fn read(path: &Path) -> Result<Option<String>> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => Ok(Some(text)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    }
+}
+
+fn parse(text: &str, path: &Path) -> Result<Vec<BuildRecord>> {
+    serde_json::from_str(text).with_context(|| format!("Malformed history in {}", path.display()))
+}
+
+fn write(path: &Path, records: &[BuildRecord]) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    let json = serde_json::to_string_pretty(records).context("Failed to serialise history")?;
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, json).with_context(|| format!("Failed to write {}", tmp.display()))?;
+    std::fs::rename(&tmp, path).with_context(|| format!("Failed to replace {}", path.display()))
+}
+
+fn history_path() -> Option<PathBuf> {
+    config_dir().map(|d| d.join(HISTORY_FILE))
+}
+
+// ─── Time and size helpers ────────────────────────────────────────────────────
+
+/// Current time in seconds since the Unix epoch.
+#[must_use]
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// `YYYY-MM-DD HH:MM UTC` for a Unix timestamp.
+#[must_use]
+pub fn format_timestamp(secs: u64) -> String {
+    let (year, month, day) = civil_date(secs);
+    let rem = secs % 86_400;
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02} UTC",
+        rem / 3_600,
+        rem % 3_600 / 60
+    )
+}
+
+/// `YYYYMMDD-HHMMSS` (UTC), sortable and safe in file names.
+#[must_use]
+pub fn format_file_stamp(secs: u64) -> String {
+    let (year, month, day) = civil_date(secs);
+    let rem = secs % 86_400;
+    format!(
+        "{year:04}{month:02}{day:02}-{:02}{:02}{:02}",
+        rem / 3_600,
+        rem % 3_600 / 60,
+        rem % 60
+    )
+}
+
+/// `HH:MM:SS` (UTC) time of day.
+#[must_use]
+pub fn format_clock(secs: u64) -> String {
+    let rem = secs % 86_400;
+    format!("{:02}:{:02}:{:02}", rem / 3_600, rem % 3_600 / 60, rem % 60)
+}
+
+/// Byte count in 1,024-based units: `512 B`, `87.3 MB`, `1.4 GB`.
+#[must_use]
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1_024 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64 / 1_024.0;
+    let mut unit = 0;
+    while value >= 1_024.0 && unit < UNITS.len() - 1 {
+        value /= 1_024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+/// UTC calendar date of a Unix timestamp (Howard Hinnant's civil_from_days).
+fn civil_date(secs: u64) -> (i64, i64, i64) {
+    let z = (secs / 86_400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Compact duration: `45s`, `12m 05s`, `1h 02m`.
+#[must_use]
+pub fn format_duration(secs: u64) -> String {
+    match secs {
+        0..=59 => format!("{secs}s"),
+        60..=3_599 => format!("{}m {:02}s", secs / 60, secs % 60),
+        _ => format!("{}h {:02}m", secs / 3_600, secs % 3_600 / 60),
+    }
+}
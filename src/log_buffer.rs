@@ -0,0 +1,197 @@
+// src/log_buffer.rs
+//
+// In-memory tail of the build log, shared by the window and the terminal
+// interface.
+//
+// Lines live in a ring buffer: appending touches only the last line, a
+// bare `\r` rewinds just that line (terminal semantics, so git/cmake/cargo
+// progress stays one updating line), and once `capacity` is reached the
+// oldest line is popped off the front.  The rewind waits for the text
+// that replaces the line, so a chunk that ends in `\r` (or a `\r\n` split
+// across chunks) never leaves the line blank.  Nothing ever rescans or reallocates
+// the whole log, so verbose builds cost the same per byte at line 100 as at
+// line 100,000.  Lines are numbered absolutely — counting the ones already
+// dropped — which is what stage sections refer to.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Arc;
+
+pub struct LogBuffer {
+    /// Never empty: the last line is the one being written.
+    lines: VecDeque<String>,
+    /// Absolute lines written to stderr, with the command that wrote them.
+    stderr: BTreeMap<usize, Arc<str>>,
+    /// Lines popped off the front so far.
+    dropped: usize,
+    capacity: usize,
+    /// A `\r` arrived after the last text of the current line: the next
+    /// text replaces the line rather than extending it.
+    rewind: bool,
+}
+
+impl LogBuffer {
+    /// An empty log keeping at most `capacity` lines.
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            lines: VecDeque::from([String::new()]),
+            stderr: BTreeMap::new(),
+            dropped: 0,
+            capacity: capacity.max(1),
+            rewind: false,
+        }
+    }
+
+    /// Append output text; `stderr_of` names the command if it came from
+    /// that command's stderr.
+    pub fn push(&mut self, text: &str, stderr_of: Option<&Arc<str>>) {
+        let mut segments = text.split('\n');
+        if let Some(first) = segments.next() {
+            self.extend_current(first, stderr_of);
+        }
+        for segment in segments {
+            self.new_line();
+            self.extend_current(segment, stderr_of);
+        }
+    }
+
+    /// Add newline-free text to the current line.  Text after a `\r`
+    /// replaces the line; a trailing `\r` only marks it for replacement.
+    fn extend_current(&mut self, segment: &str, stderr_of: Option<&Arc<str>>) {
+        let index = self.current_line();
+        let line = self.lines.back_mut().expect("log buffer is never empty");
+        let mut parts = segment.split('\r');
+        let mut part = parts.next();
+        while let Some(text) = part {
+            if !text.is_empty() {
+                if self.rewind {
+                    line.clear();
+                    self.stderr.remove(&index);
+                    self.rewind = false;
+                }
+                line.push_str(text);
+                if let Some(command) = stderr_of {
+                    self.stderr.insert(index, Arc::clone(command));
+                }
+            }
+            part = parts.next();
+            self.rewind |= part.is_some();
+        }
+    }
+
+    fn new_line(&mut self) {
+        self.rewind = false;
+        self.lines.push_back(String::new());
+        if self.lines.len() > self.capacity {
+            self.lines.pop_front();
+            self.stderr.remove(&self.dropped);
+            self.dropped += 1;
+        }
+    }
+
+    /// Lines held, including the unfinished last one.
+    #[must_use]
+    pub fn line_count(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Absolute number of the first line held.
+    #[must_use]
+    pub fn first_line(&self) -> usize {
+        self.dropped
+    }
+
+    /// Absolute number of the line being written.
+    #[must_use]
+    pub fn current_line(&self) -> usize {
+        self.dropped + self.lines.len() - 1
+    }
+
+    /// Held line `i`, counting from the oldest.
+    #[must_use]
+    pub fn line(&self, i: usize) -> &str {
+        &self.lines[i]
+    }
+
+    /// The command whose stderr wrote held line `i`, if any.
+    #[must_use]
+    pub fn stderr_of(&self, i: usize) -> Option<&Arc<str>> {
+        self.stderr.get(&(self.dropped + i))
+    }
+
+    /// Rearrange storage so `as_slice` covers every held line.
+    pub fn make_contiguous(&mut self) {
+        self.lines.make_contiguous();
+    }
+
+    /// Held lines, oldest first — all of them only after `make_contiguous`.
+    #[must_use]
+    pub fn as_slice(&self) -> &[String] {
+        self.lines.as_slices().0
+    }
+
+    /// The held log as text, one `\n` per line break.
+    #[must_use]
+    pub fn to_text(&self) -> String {
+        let mut text = String::with_capacity(self.lines.iter().map(|l| l.len() + 1).sum());
+        for (i, line) in self.lines.iter().enumerate() {
+            if i > 0 {
+                text.push('\n');
+            }
+            text.push_str(line);
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The held lines after pushing each chunk in turn.
+    fn lines_after(chunks: &[&str]) -> Vec<String> {
+        let mut log = LogBuffer::new(100);
+        for chunk in chunks {
+            log.push(chunk, None);
+        }
+        log.make_contiguous();
+        log.as_slice().to_vec()
+    }
+
+    #[test]
+    fn carriage_returns_rewind_the_line() {
+        let cases: &[(&[&str], &[&str])] = &[
+            (&["a\rb"], &["b"]),
+            (&["a\r", "b"], &["b"]),
+            (&["a\r\n"], &["a", ""]),
+            (&["a\r", "\n"], &["a", ""]),
+            (&["a\r"], &["a"]),
+            (&["10%\r20%\r", "30%\n", "done"], &["30%", "done"]),
+            (&["abc\r", "d"], &["d"]),
+        ];
+        for (chunks, expected) in cases {
+            assert_eq!(lines_after(chunks), *expected, "{chunks:?}");
+        }
+    }
+
+    #[test]
+    fn stderr_follows_the_text_that_replaced_the_line() {
+        let command: Arc<str> = Arc::from("cmake");
+        let mut log = LogBuffer::new(100);
+        log.push("warning\r", Some(&command));
+        assert!(log.stderr_of(0).is_some());
+        log.push("progress", None);
+        assert_eq!(log.line(0), "progress");
+        assert!(log.stderr_of(0).is_none());
+    }
+
+    #[test]
+    fn oldest_lines_are_dropped_at_capacity() {
+        let mut log = LogBuffer::new(3);
+        log.push("one\ntwo\nthree\nfour", None);
+        assert_eq!(log.line_count(), 3);
+        assert_eq!(log.first_line(), 1);
+        assert_eq!(log.current_line(), 3);
+        assert_eq!(log.to_text(), "two\nthree\nfour");
+    }
+}
@@ -0,0 +1,681 @@
+// src/log_view.rs
+//
+// Searching and filtering the build log.
+//
+// The on-screen log is trimmed, so search, the errors/warnings filter and
+// "jump to first error" read the build's full log file (see build_log.rs)
+// instead.  `FullLog` follows that file incrementally while a build is
+// still writing it; `LogSearch` caches which lines match and scans only
+// the lines appended since, so the panel never rescans the whole log
+// unless the query or the filter changes.
+//
+// `Sections` groups the live log by build stage (`AppMessage::StageStarted`)
+// so finished stages can be folded away.  `SectionRows` keeps the rows of
+// that view in step with the log: new lines append rows and trimmed lines
+// drop them from the front, so only folding or a new stage rebuilds them.
+
+use std::collections::VecDeque;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::ansi;
+use crate::build_log::STDERR_TAG;
+use crate::process;
+
+// ─── Severity ─────────────────────────────────────────────────────────────────
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// Recognise compiler, cmake, cargo, git and BitForge problem lines.
+/// Colour codes are ignored.
+#[must_use]
+pub fn classify(line: &str) -> Option<Severity> {
+    let line = ansi::strip(line);
+    const ERRORS: [&str; 6] = [
+        "error:",
+        "error[",
+        "CMake Error",
+        "fatal:",
+        "fatal error",
+        "❌",
+    ];
+    const WARNINGS: [&str; 4] = ["warning:", "warning[", "CMake Warning", "⚠"];
+
+    if ERRORS.iter().any(|p| line.contains(p)) {
+        Some(Severity::Error)
+    } else if WARNINGS.iter().any(|p| line.contains(p)) {
+        Some(Severity::Warning)
+    } else {
+        None
+    }
+}
+
+// ─── Full log file ────────────────────────────────────────────────────────────
+
+/// Lines of a build log file, without the per-line timestamps and stream
+/// tags.
+#[derive(Default)]
+pub struct FullLog {
+    path: Option<PathBuf>,
+    /// Bytes consumed so far; a partial last line is left for next time.
+    offset: u64,
+    lines: Vec<String>,
+    /// Per line: whether it was written to stderr.
+    stderr: Vec<bool>,
+}
+
+impl FullLog {
+    /// Pick up whatever has been appended to `path` since the last call,
+    /// starting over if the file changed.
+    pub fn refresh(&mut self, path: &Path) {
+        if self.path.as_deref() != Some(path) {
+            *self = Self {
+                path: Some(path.to_path_buf()),
+                ..Self::default()
+            };
+        }
+        let len = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        if len <= self.offset {
+            return;
+        }
+        let Ok(mut file) = std::fs::File::open(path) else {
+            return;
+        };
+        if file.seek(SeekFrom::Start(self.offset)).is_err() {
+            return;
+        }
+        let mut bytes = Vec::new();
+        if file.read_to_end(&mut bytes).is_err() {
+            return;
+        }
+        let Some(end) = bytes.iter().rposition(|&b| b == b'\n') else {
+            return;
+        };
+        self.offset += end as u64 + 1;
+        for line in String::from_utf8_lossy(&bytes[..end]).split('\n') {
+            if line.starts_with("# ") {
+                continue; // file header
+            }
+            let line = strip_timestamp(line);
+            let (line, stderr) = match line.strip_prefix(STDERR_TAG) {
+                Some(rest) => (rest, true),
+                None => (line, false),
+            };
+            self.lines.push(line.to_owned());
+            self.stderr.push(stderr);
+        }
+    }
+
+    #[must_use]
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    #[must_use]
+    pub fn is_stderr(&self, line: usize) -> bool {
+        self.stderr.get(line).copied().unwrap_or(false)
+    }
+}
+
+/// Remove the `[HH:MM:SS] ` prefix build_log.rs writes on every line.
+fn strip_timestamp(line: &str) -> &str {
+    let bytes = line.as_bytes();
+    if bytes.len() >= 11 && bytes[0] == b'[' && bytes[9] == b']' && bytes[10] == b' ' {
+        &line[11..]
+    } else {
+        line
+    }
+}
+
+// ─── Search ───────────────────────────────────────────────────────────────────
+
+/// Search / filter state over a list of lines.
+#[derive(Default)]
+pub struct LogSearch {
+    pub query: String,
+    /// Show only error and warning lines.
+    pub problems_only: bool,
+    /// Index into `matches` of the highlighted match.
+    pub current: usize,
+    /// Indices of the lines shown, after filtering.
+    rows: Vec<usize>,
+    /// Indices into `rows` of the lines containing `query`.
+    matches: Vec<usize>,
+    /// Query and filter the cache was built for, and the lowercase query.
+    key: Option<(String, bool)>,
+    needle: String,
+    /// Absolute number of the first line scanned, and how many have been.
+    first_line: usize,
+    scanned: usize,
+}
+
+impl LogSearch {
+    #[must_use]
+    pub fn is_active(&self) -> bool {
+        !self.query.is_empty() || self.problems_only
+    }
+
+    /// Bring the visible rows and matches up to date with `lines`, whose
+    /// first line is absolute line `first_line`.  Only lines appended since
+    /// the last call are scanned, plus the last one again as it may still
+    /// have been being written; a new query or filter, or lines trimmed
+    /// from the front, rescan them all.
+    pub fn refresh(&mut self, lines: &[String], first_line: usize) {
+        let key_changed = !matches!(
+            &self.key,
+            Some((query, problems_only))
+                if *query == self.query && *problems_only == self.problems_only
+        );
+        if key_changed {
+            self.key = Some((self.query.clone(), self.problems_only));
+            self.needle = self.query.to_lowercase();
+            // New lines arriving keep the current match; a new query restarts.
+            self.current = 0;
+        }
+        if key_changed || first_line != self.first_line || lines.len() < self.scanned {
+            self.first_line = first_line;
+            self.restart();
+        }
+
+        let from = self.scanned.saturating_sub(1);
+        if self.rows.last() == Some(&from) {
+            if self.matches.last() == Some(&(self.rows.len() - 1)) {
+                self.matches.pop();
+            }
+            self.rows.pop();
+        }
+        for (i, line) in lines.iter().enumerate().skip(from) {
+            if self.problems_only && classify(line).is_none() {
+                continue;
+            }
+            if !self.needle.is_empty() && ansi::strip(line).to_lowercase().contains(&self.needle) {
+                self.matches.push(self.rows.len());
+            }
+            self.rows.push(i);
+        }
+        self.scanned = lines.len();
+        if self.current >= self.matches.len() {
+            self.current = 0;
+        }
+    }
+
+    /// Forget the scanned lines, for when they are replaced rather than
+    /// appended to; the next `refresh` scans them all.
+    pub fn restart(&mut self) {
+        self.rows.clear();
+        self.matches.clear();
+        self.scanned = 0;
+    }
+
+    /// Line indices to display, in order.
+    #[must_use]
+    pub fn rows(&self) -> &[usize] {
+        &self.rows
+    }
+
+    #[must_use]
+    pub fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    /// Row of the current match, if any.
+    #[must_use]
+    pub fn current_row(&self) -> Option<usize> {
+        self.matches.get(self.current).copied()
+    }
+
+    #[must_use]
+    pub fn is_match(&self, row: usize) -> bool {
+        self.matches.binary_search(&row).is_ok()
+    }
+
+    /// Move to the next (or previous) match, wrapping around, and return
+    /// its row.
+    pub fn step(&mut self, forward: bool) -> Option<usize> {
+        let n = self.matches.len();
+        if n == 0 {
+            return None;
+        }
+        self.current = if forward {
+            (self.current + 1) % n
+        } else {
+            (self.current + n - 1) % n
+        };
+        self.current_row()
+    }
+
+    /// Row of the first error line, if one is shown.  An error a command
+    /// wrote to stderr wins over an earlier one in ordinary output, which
+    /// is more often a test name or an echoed flag than a diagnostic.
+    #[must_use]
+    pub fn first_error_row(
+        &self,
+        lines: &[String],
+        is_stderr: impl Fn(usize) -> bool,
+    ) -> Option<usize> {
+        let is_error = |i: usize| classify(&lines[i]) == Some(Severity::Error);
+        self.rows
+            .iter()
+            .position(|&i| is_error(i) && is_stderr(i))
+            .or_else(|| self.rows.iter().position(|&i| is_error(i)))
+    }
+}
+
+// ─── Stage sections ───────────────────────────────────────────────────────────
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StageStatus {
+    Running,
+    Succeeded,
+    Failed,
+}
+
+pub struct Section {
+    pub title: String,
+    pub status: StageStatus,
+    /// Absolute log line (counting trimmed lines) the stage starts at.
+    pub first_line: usize,
+    pub collapsed: bool,
+    started: Instant,
+    finished: Option<Instant>,
+    /// `process::paused_time` at the start and end of the stage.
+    paused_at_start: Duration,
+    paused_at_finish: Option<Duration>,
+}
+
+impl Section {
+    /// Time the stage has been running, not counting pauses.
+    #[must_use]
+    pub fn elapsed(&self) -> Duration {
+        let paused = self
+            .paused_at_finish
+            .unwrap_or_else(process::paused_time)
+            .saturating_sub(self.paused_at_start);
+        self.finished
+            .unwrap_or_else(Instant::now)
+            .duration_since(self.started)
+            .saturating_sub(paused)
+    }
+}
+
+/// One row of the sectioned log view.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Row {
+    /// Header of `sections()[i]`.
+    Header(usize),
+    /// Absolute log line `i` (counting trimmed lines).
+    Line(usize),
+}
+
+/// The stages of the current build, in order.
+#[derive(Default)]
+pub struct Sections {
+    sections: Vec<Section>,
+    /// Bumped whenever the rows could change for the same lines.
+    revision: u64,
+}
+
+impl Sections {
+    /// Forget the previous build's stages; its lines show ungrouped.
+    pub fn clear(&mut self) {
+        self.sections.clear();
+        self.revision += 1;
+    }
+
+    #[must_use]
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    #[must_use]
+    pub fn sections(&self) -> &[Section] {
+        &self.sections
+    }
+
+    pub fn toggle(&mut self, index: usize) {
+        if let Some(section) = self.sections.get_mut(index) {
+            section.collapsed = !section.collapsed;
+            self.revision += 1;
+        }
+    }
+
+    /// Begin a stage at absolute line `first_line`.  The previous stage is
+    /// done, and folds away since it succeeded.
+    pub fn start(&mut self, title: String, first_line: usize) {
+        self.finish_running(true);
+        self.sections.push(Section {
+            title,
+            status: StageStatus::Running,
+            first_line,
+            collapsed: false,
+            started: Instant::now(),
+            finished: None,
+            paused_at_start: process::paused_time(),
+            paused_at_finish: None,
+        });
+        self.revision += 1;
+    }
+
+    /// Settle the running stage: successful stages collapse, a failed one
+    /// stays open.
+    pub fn finish_running(&mut self, success: bool) {
+        if let Some(last) = self
+            .sections
+            .last_mut()
+            .filter(|s| s.status == StageStatus::Running)
+        {
+            last.status = if success {
+                StageStatus::Succeeded
+            } else {
+                StageStatus::Failed
+            };
+            last.finished = Some(Instant::now());
+            last.paused_at_finish = Some(process::paused_time());
+            last.collapsed = success;
+            self.revision += 1;
+        }
+    }
+}
+
+/// Rows of the sectioned live log for a buffer that only grows at the end
+/// and is trimmed at the front, omitting the lines of collapsed stages.
+#[derive(Default)]
+pub struct SectionRows {
+    rows: VecDeque<Row>,
+    /// Absolute lines covered: `start..end`.
+    start: usize,
+    end: usize,
+    /// First section whose header is not among the rows yet.
+    next: usize,
+    /// `Sections::revision` the rows were built for.
+    revision: Option<u64>,
+}
+
+impl SectionRows {
+    /// Bring the rows up to date with a buffer of `line_count` lines whose
+    /// first line is absolute line `first_line`.
+    pub fn update(&mut self, sections: &Sections, first_line: usize, line_count: usize) {
+        let end = first_line + line_count;
+        if self.revision != Some(sections.revision())
+            || first_line < self.start
+            || first_line >= self.end
+            || end < self.end
+        {
+            self.rebuild(sections, first_line);
+        }
+        self.trim(sections, first_line);
+        self.append(sections, end);
+    }
+
+    #[must_use]
+    pub fn rows(&self) -> &VecDeque<Row> {
+        &self.rows
+    }
+
+    /// Start over at `first_line`.  Of the stages that began at or before
+    /// it, only the latest still has lines on screen.
+    fn rebuild(&mut self, sections: &Sections, first_line: usize) {
+        self.revision = Some(sections.revision());
+        let sections = &sections.sections;
+        let mut next = 0;
+        while next < sections.len() && sections[next].first_line <= first_line {
+            next += 1;
+        }
+        self.rows.clear();
+        self.rows.extend(next.checked_sub(1).map(Row::Header));
+        self.start = first_line;
+        self.end = first_line;
+        self.next = next;
+    }
+
+    /// Drop the rows of lines before `first_line`.  Of the headers of
+    /// stages that began by then, only the latest's stays.
+    fn trim(&mut self, sections: &Sections, first_line: usize) {
+        self.start = first_line;
+        let mut header = None;
+        while let Some(&row) = self.rows.front() {
+            match row {
+                Row::Line(line) if line < first_line => {}
+                Row::Header(i) if sections.sections[i].first_line <= first_line => {
+                    header = Some(row);
+                }
+                _ => break,
+            }
+            self.rows.pop_front();
+        }
+        if let Some(header) = header {
+            self.rows.push_front(header);
+        }
+    }
+
+    /// Add rows for the lines up to `end`, with the headers of the stages
+    /// that begin among them.
+    fn append(&mut self, sections: &Sections, end: usize) {
+        let sections = &sections.sections;
+        for line in self.end..end {
+            while self.next < sections.len() && sections[self.next].first_line <= line {
+                self.rows.push_back(Row::Header(self.next));
+                self.next += 1;
+            }
+            let collapsed = self
+                .next
+                .checked_sub(1)
+                .is_some_and(|current| sections[current].collapsed);
+            if !collapsed {
+                self.rows.push_back(Row::Line(line));
+            }
+        }
+        self.end = self.end.max(end);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &[&str]) -> Vec<String> {
+        text.iter().map(|line| (*line).to_owned()).collect()
+    }
+
+    #[test]
+    fn problem_lines_are_classified() {
+        let cases: &[(&str, Option<Severity>)] = &[
+            (
+                "src/init.cpp:12:5: error: use of undeclared identifier",
+                Some(Severity::Error),
+            ),
+            ("error[E0308]: mismatched types", Some(Severity::Error)),
+            (
+                "CMake Error at CMakeLists.txt:40 (find_package):",
+                Some(Severity::Error),
+            ),
+            (
+                "fatal: Remote branch v99 not found in upstream origin",
+                Some(Severity::Error),
+            ),
+            (
+                "src/net.cpp:3:10: fatal error: 'boost/signals2.hpp' file not found",
+                Some(Severity::Error),
+            ),
+            ("❌ Build failed", Some(Severity::Error)),
+            (
+                "\x1b[1;31merror\x1b[0m\x1b[1m:\x1b[0m linking failed",
+                Some(Severity::Error),
+            ),
+            (
+                "src/util.h:8:1: warning: unused variable 'x'",
+                Some(Severity::Warning),
+            ),
+            (
+                "warning[unused_imports]: unused import",
+                Some(Severity::Warning),
+            ),
+            (
+                "CMake Warning (dev) in CMakeLists.txt:",
+                Some(Severity::Warning),
+            ),
+            ("⚠ Low disk space", Some(Severity::Warning)),
+            ("warning: treating error: as text", Some(Severity::Error)),
+            (
+                "[ 42%] Building CXX object src/CMakeFiles/bitcoin_node.dir/init.cpp.o",
+                None,
+            ),
+            ("test_error_handling ... ok", None),
+        ];
+        for (line, expected) in cases {
+            assert_eq!(classify(line), *expected, "{line}");
+        }
+    }
+
+    #[test]
+    fn search_steps_through_matches_and_wraps() {
+        let log = lines(&["Cloning", "make all", "Make depends", "done", "MAKE check"]);
+        let mut search = LogSearch {
+            query: "make".to_owned(),
+            ..LogSearch::default()
+        };
+        search.refresh(&log, 0);
+        assert_eq!(search.rows(), &[0, 1, 2, 3, 4]);
+        assert_eq!(search.match_count(), 3);
+        assert_eq!(search.current_row(), Some(1));
+        assert!(search.is_match(2) && !search.is_match(3));
+
+        assert_eq!(search.step(true), Some(2));
+        assert_eq!(search.step(true), Some(4));
+        assert_eq!(search.step(true), Some(1));
+        assert_eq!(search.step(false), Some(4));
+
+        search.query = "nothing".to_owned();
+        search.refresh(&log, 0);
+        assert_eq!(search.match_count(), 0);
+        assert_eq!(search.step(true), None);
+    }
+
+    #[test]
+    fn problems_only_shows_errors_and_warnings() {
+        let log = lines(&[
+            "-- Configuring done",
+            "src/a.cpp:1: warning: unused",
+            "[ 50%] Building",
+            "src/b.cpp:2: error: bad",
+            "src/b.cpp:9: error: worse",
+        ]);
+        let mut search = LogSearch {
+            problems_only: true,
+            ..LogSearch::default()
+        };
+        search.refresh(&log, 0);
+        assert_eq!(search.rows(), &[1, 3, 4]);
+
+        search.query = "error".to_owned();
+        search.refresh(&log, 0);
+        assert_eq!(search.match_count(), 2);
+        assert_eq!(search.current_row(), Some(1));
+
+        let stderr = |i: usize| i == 4;
+        assert_eq!(search.first_error_row(&log, stderr), Some(2));
+        assert_eq!(search.first_error_row(&log, |_| false), Some(1));
+    }
+
+    #[test]
+    fn search_scans_appended_lines_and_keeps_the_current_match() {
+        let mut log = lines(&["error: one", "ok", "error: tw"]);
+        let mut search = LogSearch {
+            query: "error".to_owned(),
+            ..LogSearch::default()
+        };
+        search.refresh(&log, 0);
+        search.step(true);
+        assert_eq!(search.current_row(), Some(2));
+
+        // The unfinished last line is scanned again as it grows.
+        log[2].push('o');
+        log.extend(lines(&["fine", "error: three"]));
+        search.refresh(&log, 0);
+        assert_eq!(search.rows(), &[0, 1, 2, 3, 4]);
+        assert_eq!(search.match_count(), 3);
+        assert_eq!(search.current_row(), Some(2));
+
+        // Trimmed from the front: everything is rescanned.
+        log.remove(0);
+        search.refresh(&log, 1);
+        assert_eq!(search.match_count(), 2);
+        assert_eq!(search.rows().len(), 4);
+
+        // Replaced by a new log.
+        search.restart();
+        search.refresh(&lines(&["nothing here"]), 0);
+        assert_eq!(search.match_count(), 0);
+        assert_eq!(search.current_row(), None);
+    }
+
+    /// Rows built from scratch for the given buffer.
+    fn fresh(sections: &Sections, first_line: usize, line_count: usize) -> Vec<Row> {
+        let mut rows = SectionRows::default();
+        rows.update(sections, first_line, line_count);
+        rows.rows().iter().copied().collect()
+    }
+
+    #[test]
+    fn section_rows_follow_the_log() {
+        let mut sections = Sections::default();
+        let mut rows = SectionRows::default();
+        sections.start("Configure".to_owned(), 0);
+        rows.update(&sections, 0, 3);
+        assert_eq!(
+            rows.rows(),
+            &[Row::Header(0), Row::Line(0), Row::Line(1), Row::Line(2)]
+        );
+
+        sections.start("Compile".to_owned(), 3);
+        rows.update(&sections, 0, 5);
+        assert_eq!(
+            rows.rows(),
+            &[Row::Header(0), Row::Header(1), Row::Line(3), Row::Line(4)]
+        );
+
+        sections.toggle(0);
+        rows.update(&sections, 0, 5);
+        assert_eq!(rows.rows().len(), 7);
+    }
+
+    #[test]
+    fn section_rows_match_a_rebuild_as_lines_arrive_and_trim() {
+        let mut sections = Sections::default();
+        sections.start("Clone".to_owned(), 0);
+        sections.start("Configure".to_owned(), 2);
+        sections.toggle(0);
+        sections.start("Compile".to_owned(), 6);
+        sections.start("Install".to_owned(), 6);
+        sections.start("Test".to_owned(), 12);
+
+        let mut rows = SectionRows::default();
+        // (first line, line count) as the buffer grows, then holds 5.
+        let states = [
+            (0, 1),
+            (0, 2),
+            (0, 5),
+            (1, 5),
+            (3, 5),
+            (5, 5),
+            (6, 5),
+            (8, 5),
+            (9, 5),
+            (14, 5),
+            (30, 5),
+        ];
+        for (first_line, line_count) in states {
+            rows.update(&sections, first_line, line_count);
+            assert_eq!(
+                rows.rows().iter().copied().collect::<Vec<_>>(),
+                fresh(&sections, first_line, line_count),
+                "lines {first_line} + {line_count}"
+            );
+        }
+    }
+}
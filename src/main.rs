@@ -1,30 +1,30 @@
 // src/main.rs — BitForge entry point.
 
+mod ansi;
 mod app;
+mod build_log;
+mod cli;
 mod compiler;
 mod deps;
+mod diagnose;
 mod env_setup;
 mod github;
+mod history;
+mod log_buffer;
+mod log_view;
 mod messages;
+mod options;
 mod process;
+mod retry;
+mod settings;
+mod toolchain;
+mod tui;
 
 use std::sync::Arc;
 
 use app::BitForgeApp;
-use env_setup::{brew_prefix, find_brew, setup_build_environment};
 
 fn main() -> eframe::Result<()> {
-    // ── 0. Widen PATH for child processes ─────────────────────────────────────
-    // SAFETY: single-threaded at this point.
-    {
-        let brew = find_brew();
-        let pfx = brew.as_deref().map(brew_prefix);
-        let env = setup_build_environment(pfx.as_deref());
-        if let Some(path) = env.get("PATH") {
-            std::env::set_var("PATH", path);
-        }
-    }
-
     // ── 1. Tokio runtime ──────────────────────────────────────────────────────
     let worker_threads = std::thread::available_parallelism()
         .map(|n| n.get().min(8))
@@ -38,11 +38,18 @@ fn main() -> eframe::Result<()> {
             .expect("Failed to create tokio runtime"),
     );
 
-    // ── 2. Channels ───────────────────────────────────────────────────────────
+    // ── 2. Headless commands ──────────────────────────────────────────────────
+    // Any arguments select the command-line front-end instead of the window.
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(code) = cli::run(&args, &runtime) {
+        std::process::exit(code);
+    }
+
+    // ── 3. Channels ───────────────────────────────────────────────────────────
     let (msg_tx, msg_rx) = std::sync::mpsc::channel::<messages::AppMessage>();
     let (confirm_tx, confirm_rx) = std::sync::mpsc::channel::<messages::ConfirmRequest>();
 
-    // ── 3. Window ─────────────────────────────────────────────────────────────
+    // ── 4. Window ─────────────────────────────────────────────────────────────
     let native_options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_title("BitForge")
@@ -52,7 +59,7 @@ fn main() -> eframe::Result<()> {
         ..Default::default()
     };
 
-    // ── 4. Run on main thread ─────────────────────────────────────────────────
+    // ── 5. Run on main thread ─────────────────────────────────────────────────
     eframe::run_native(
         "BitForge",
         native_options,
@@ -7,9 +7,16 @@
 // Also provides `log_msg`, the single shared helper used by every module
 // to push a line into the UI terminal, eliminating the per-module duplicate.
 
+use std::path::PathBuf;
 use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::oneshot;
 
+use crate::diagnose::Diagnosis;
+use crate::history::BuildRecord;
+use crate::process::ResourceUsage;
+
 // ─── AppMessage ──────────────────────────────────────────────────────────────
 
 #[derive(Debug)]
@@ -17,6 +24,14 @@ pub enum AppMessage {
     /// Append text to the dark terminal log widget.
     Log(String),
 
+    /// Output of a child process started by `run_command`, tagged with the
+    /// pipe it came from and the command line that produced it.
+    Output {
+        command: Arc<str>,
+        stream: Stream,
+        text: String,
+    },
+
     /// Set the progress bar value (0.0 – 1.0).
     Progress(f32),
 
@@ -26,6 +41,10 @@ pub enum AppMessage {
     /// Populate the Electrs version combobox.
     ElectrsVersionsLoaded(Vec<String>),
 
+    /// Every Homebrew formula the dependency check found installed, as
+    /// `(name, version)`; replaces the previous check's list.
+    InstalledPackages(Vec<(String, String)>),
+
     /// Show an informational / error overlay (no reply needed).
     ShowDialog {
         title: String,
@@ -33,17 +52,66 @@ pub enum AppMessage {
         is_error: bool,
     },
 
+    /// A build failed: show `message` as an error, with the known cause
+    /// and its fix when `diagnose` recognised one.
+    BuildFailed {
+        message: String,
+        diagnosis: Option<Diagnosis>,
+    },
+
+    /// The running command `command` has printed nothing for `silent_for`
+    /// (sent once a minute); `None` once it prints again or exits.
+    Stalled {
+        command: Arc<str>,
+        silent_for: Option<Duration>,
+    },
+
+    /// A command started by `run_command` exited (or was killed) after
+    /// using this much CPU and memory.
+    Usage(ResourceUsage),
+
+    /// A build stage began; `Log` output up to the next stage belongs to it.
+    StageStarted(String),
+
+    /// The build's last stage ended, successfully or not.
+    BuildFinished { success: bool },
+
+    /// The running build's full log is being written to this file.
+    LogFile(PathBuf),
+
+    /// A build finished and was added to the history file.
+    BuildRecorded(Box<BuildRecord>),
+
     /// A background task completed — re-enable the Compile button.
     TaskDone,
 }
 
+/// Which pipe of a child process an `AppMessage::Output` chunk was read from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
 // ─── ConfirmRequest ───────────────────────────────────────────────────────────
 
 pub struct ConfirmRequest {
     pub title: String,
     pub message: String,
-    /// UI sends `true` (Yes) or `false` (No) back through this channel.
-    pub response_tx: oneshot::Sender<bool>,
+    /// Checklist rows; empty for a plain Yes/No question.
+    pub items: Vec<ConfirmItem>,
+    /// UI sends `Some(ticked)` — one flag per item — on Yes, or `None` on No.
+    pub response_tx: oneshot::Sender<Option<Vec<bool>>>,
+}
+
+/// One tickable row of a checklist confirmation.
+#[derive(Clone, Debug)]
+pub struct ConfirmItem {
+    pub label: String,
+    /// Short explanation shown next to the label.
+    pub detail: String,
+    /// Initial tick state.
+    pub checked: bool,
 }
 
 // ─── Shared log helper ────────────────────────────────────────────────────────
@@ -0,0 +1,70 @@
+// src/options.rs
+//
+// What the user asked to build: target, versions and optional features.
+// Shared by the UI, the dependency checker and the compiler so that all
+// three agree on exactly which packages and cmake flags a build implies.
+
+use serde::{Deserialize, Serialize};
+
+// ─── Target ───────────────────────────────────────────────────────────────────
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Target {
+    #[default]
+    Bitcoin,
+    Electrs,
+    Both,
+}
+
+impl Target {
+    /// Every target in combobox order.
+    pub const ALL: [Target; 3] = [Target::Bitcoin, Target::Electrs, Target::Both];
+
+    /// Human-readable label, also used in dialogs and the log.
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            Target::Bitcoin => "Bitcoin",
+            Target::Electrs => "Electrs",
+            Target::Both => "Both",
+        }
+    }
+
+    #[must_use]
+    pub fn includes_bitcoin(self) -> bool {
+        matches!(self, Target::Bitcoin | Target::Both)
+    }
+
+    #[must_use]
+    pub fn includes_electrs(self) -> bool {
+        matches!(self, Target::Electrs | Target::Both)
+    }
+}
+
+// ─── Build options ────────────────────────────────────────────────────────────
+
+/// Optional build features. Everything defaults to off, matching the
+/// node-only build BitForge has always produced.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BuildOptions {
+    /// `-DENABLE_WALLET=ON` — descriptor wallet, needs SQLite.
+    pub wallet: bool,
+    /// `-DWITH_ZMQ=ON` — ZMQ block/tx notifications, needs ZeroMQ.
+    pub zmq: bool,
+    /// rustup toolchain for Electrs (e.g. `1.80.0`, `stable`).  Empty means
+    /// honour the checkout's `rust-toolchain` file, else rustup's default.
+    pub rust_toolchain: String,
+}
+
+// ─── Selection ────────────────────────────────────────────────────────────────
+
+/// A complete description of one requested build.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BuildSelection {
+    pub target: Target,
+    pub bitcoin_version: String,
+    pub electrs_version: String,
+    pub options: BuildOptions,
+}
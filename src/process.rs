@@ -1,161 +1,873 @@
 // src/process.rs
 //
-// `run_command`: spawn a child via `sh -c`, stream stdout+stderr to the UI.
+// `CommandSpec`: program, argv, cwd, env and time limit of a child process.
+// `run_command`: spawn a `CommandSpec` directly (no shell), stream
+//                stdout+stderr to the UI, each chunk tagged with its stream
+//                and command; afterwards log and report its CPU time and
+//                peak memory (`ResourceUsage`).  A failure is a
+//                `CommandError` carrying the tail of the output.
+// `capture`:     run a `CommandSpec` and return its output instead of
+//                streaming it.
 // `probe`:       run a command and capture its output (no logging).
 //
 // KEY DESIGN: we read stdout/stderr as raw byte chunks rather than lines.
 // This ensures that:
 //   • git's carriage-return-based progress ("\rReceiving 50%") is shown live.
-//   • cmake/cargo output without trailing newlines is not buffered indefinitely.
+//   • cmake/cargo output without trailing newlines is not buffered indefinitely
+//     (a partial line is flushed after a short quiet period).
 //   • No output is ever silently swallowed in the BufReader internal buffer.
 
+use std::borrow::Cow;
 use std::collections::HashMap;
-use std::path::Path;
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::path::PathBuf;
+use std::process::{ExitStatus, Output, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::mpsc::Sender;
+use std::sync::{Arc, LazyLock, Mutex, RwLock};
+use std::time::{Duration, Instant};
 
-use anyhow::{bail, Context, Result};
-use tokio::io::AsyncReadExt;
-use tokio::process::Command;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::process::{ChildStderr, ChildStdout, Command};
+use tokio::sync::watch;
 
-use crate::messages::AppMessage;
+use crate::ansi;
+use crate::history::{format_bytes, format_duration};
+use crate::messages::{log_msg, AppMessage, Stream};
 
-/// Execute `cmd` in a shell, streaming every byte of output to `log_tx`.
+/// A command silent for this long is reported as stalled, and again each
+/// time the silence grows by as much.
+const STALL_NOTICE: Duration = Duration::from_secs(60);
+/// A partial line is forwarded after its stream has been quiet this long…
+const PARTIAL_LINE_FLUSH: Duration = Duration::from_millis(100);
+/// …or once it grows past this many bytes.
+const MAX_PARTIAL_LINE: usize = 64 * 1024;
+/// How much of a failed command's output its `CommandError` keeps.
+const OUTPUT_TAIL: usize = 64 * 1024;
+/// `probe` gives up on a command still running after this long.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Whether commands are paused, and for how long they have been.  One
+/// switch for the whole app, as only one task runs commands at a time.
+static PAUSE: LazyLock<watch::Sender<PauseClock>> =
+    LazyLock::new(|| watch::channel(PauseClock::default()).0);
+/// Priority the next command starts at.
+static PRIORITY: RwLock<Priority> = RwLock::new(Priority::Normal);
+/// Niceness of background commands; the usual default of nice(1).
+#[cfg(unix)]
+const BACKGROUND_NICE: libc::c_int = 10;
+
+// ─── Command spec ─────────────────────────────────────────────────────────────
+
+/// A child process to run: program, arguments, working directory, complete
+/// environment and optional time limits.  Spawned directly — never through
+/// a shell — so arguments need no quoting and cannot inject commands.
+#[derive(Clone, Debug)]
+pub struct CommandSpec {
+    program: OsString,
+    args: Vec<OsString>,
+    cwd: Option<PathBuf>,
+    /// Replaces the child's inherited environment.
+    env: HashMap<String, String>,
+    /// Wall-clock limit.
+    timeout: Option<Duration>,
+    /// Longest allowed stretch without any output.
+    idle_timeout: Option<Duration>,
+}
+
+impl CommandSpec {
+    pub fn new(program: impl AsRef<OsStr>, env: &HashMap<String, String>) -> Self {
+        Self {
+            program: program.as_ref().to_owned(),
+            args: Vec::new(),
+            cwd: None,
+            env: env.clone(),
+            timeout: None,
+            idle_timeout: None,
+        }
+    }
+
+    #[must_use]
+    pub fn arg(mut self, arg: impl AsRef<OsStr>) -> Self {
+        self.args.push(arg.as_ref().to_owned());
+        self
+    }
+
+    #[must_use]
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.args
+            .extend(args.into_iter().map(|a| a.as_ref().to_owned()));
+        self
+    }
+
+    /// Run in `dir` instead of the current directory.
+    #[must_use]
+    pub fn in_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cwd = Some(dir.into());
+        self
+    }
+
+    /// Kill the command if it has not finished after `limit`.
+    #[must_use]
+    pub fn timeout(mut self, limit: Duration) -> Self {
+        self.timeout = Some(limit);
+        self
+    }
+
+    /// Kill the command if it prints nothing for `limit`.
+    #[must_use]
+    pub fn idle_timeout(mut self, limit: Duration) -> Self {
+        self.idle_timeout = Some(limit);
+        self
+    }
+
+    /// The command line, then where it runs and its time limits on
+    /// indented lines — for build plans.
+    #[must_use]
+    pub fn describe(&self) -> String {
+        let mut text = format!("$ {self}");
+        if let Some(dir) = &self.cwd {
+            text.push_str(&format!("\n    in {}", dir.display()));
+        }
+        let limits: Vec<String> = [
+            self.timeout
+                .map(|t| format!("{} in total", format_duration(t.as_secs()))),
+            self.idle_timeout
+                .map(|t| format!("{} without output", format_duration(t.as_secs()))),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        if !limits.is_empty() {
+            text.push_str(&format!("\n    killed after {}", limits.join(" or ")));
+        }
+        text
+    }
+
+    /// The process to spawn: stdout and stderr piped, in its own process
+    /// group (so a timeout also takes out helpers such as git-remote-https
+    /// or the compilers cmake is waiting on) and at the current `priority`.
+    fn command(&self) -> std::process::Command {
+        let mut builder = std::process::Command::new(&self.program);
+        builder
+            .args(&self.args)
+            .env_clear()
+            .envs(&self.env)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            builder.process_group(0);
+            if priority().is_background() {
+                // SAFETY: `lower_priority` only makes async-signal-safe calls.
+                unsafe {
+                    builder.pre_exec(lower_priority);
+                }
+            }
+        }
+        if let Some(dir) = &self.cwd {
+            builder.current_dir(dir);
+        }
+        builder
+    }
+}
+
+/// Shell-quoted form for logs and dialogs; pasting it into a shell runs the
+/// same command.
+impl fmt::Display for CommandSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&shell_quote(&self.program.to_string_lossy()))?;
+        for arg in &self.args {
+            write!(f, " {}", shell_quote(&arg.to_string_lossy()))?;
+        }
+        Ok(())
+    }
+}
+
+/// `s` as a single POSIX shell word, quoted only when it needs to be.
+fn shell_quote(s: &str) -> Cow<'_, str> {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "-_./:=,+@%".contains(c);
+    if !s.is_empty() && s.chars().all(plain) {
+        Cow::Borrowed(s)
+    } else {
+        Cow::Owned(format!("'{}'", s.replace('\'', r"'\''")))
+    }
+}
+
+// ─── Running ──────────────────────────────────────────────────────────────────
+
+/// Run `spec`, streaming every byte of output to `log_tx`.
 ///
-/// * `cwd` – optional working directory for the child process.
-/// * `env` – complete environment (replaces the child's inherited env).
+/// Once the command has been silent for `STALL_NOTICE`, `AppMessage::Stalled`
+/// reports it every `STALL_NOTICE` until output resumes.  Past either of the spec's
+/// time limits its whole process group is killed.  While `set_paused` holds
+/// it, the group is stopped and the time limits, stall notices and reported
+/// wall time all leave the pause out; a command due to start waits for the
+/// resume.
 ///
-/// Returns `Ok(())` on exit code 0; `Err` on non-zero exit or spawn failure.
-pub async fn run_command(
-    cmd: &str,
-    cwd: Option<&Path>,
-    env: &HashMap<String, String>,
-    log_tx: &Sender<AppMessage>,
-) -> Result<()> {
-    log_tx.send(AppMessage::Log(format!("\n$ {cmd}\n"))).ok();
-
-    let mut builder = Command::new("sh");
-    builder
-        .arg("-c")
-        .arg(cmd)
-        .env_clear()
-        .envs(env)
-        .stdout(std::process::Stdio::piped())
-        .stderr(std::process::Stdio::piped())
-        // No orphan processes if this task is cancelled.
-        .kill_on_drop(true);
-
-    if let Some(dir) = cwd {
-        builder.current_dir(dir);
-    }
-
-    let mut child = builder
+/// Returns `Ok(())` on exit code 0; `Err` on non-zero exit, spawn failure or
+/// timeout.  A command that ran and failed is a `CommandError`, for
+/// `diagnose` to look through its output.
+pub async fn run_command(spec: &CommandSpec, log_tx: &Sender<AppMessage>) -> Result<()> {
+    let display = spec.to_string();
+    let mut pause = PAUSE.subscribe();
+    if pause.borrow().paused() {
+        log_msg(log_tx, "\n⏸  Paused — waiting to resume…\n");
+        let _ = pause.wait_for(|clock| !clock.paused()).await;
+    }
+    log_msg(log_tx, &format!("\n$ {display}\n"));
+
+    let started = Instant::now();
+    let paused_before = paused_time();
+    // Time the command has been allowed to run.
+    let active = |clock: &PauseClock| {
+        started
+            .elapsed()
+            .saturating_sub(clock.elapsed().saturating_sub(paused_before))
+    };
+    let mut child = spec
+        .command()
         .spawn()
-        .with_context(|| format!("Failed to spawn: {cmd}"))?;
+        .with_context(|| format!("Failed to spawn: {display}"))?;
+    // No orphan processes if this task is cancelled.
+    let mut group = ProcessGroup::new(child.id());
 
-    let stdout = child.stdout.take().context("stdout not captured")?;
-    let stderr = child.stderr.take().context("stderr not captured")?;
+    let stdout = ChildStdout::from_std(child.stdout.take().context("stdout not captured")?)
+        .context("Failed to read stdout")?;
+    let stderr = ChildStderr::from_std(child.stderr.take().context("stderr not captured")?)
+        .context("Failed to read stderr")?;
 
     // Drain stdout and stderr as raw byte chunks so that:
     //   - \r-terminated progress lines (git, cmake) appear immediately.
     //   - Large pipe buffers never deadlock the child process.
-    // Each chunk is sanitised: \r not followed by \n becomes \n so the
-    // terminal-style log displays correctly.
-    let tx_out = log_tx.clone();
-    let tx_err = log_tx.clone();
-
-    let stdout_task = tokio::spawn(drain_reader(stdout, tx_out));
-    let stderr_task = tokio::spawn(drain_reader(stderr, tx_err));
+    // Each stream is forwarded a whole line at a time, so the two never
+    // interleave mid-line.
+    let command: Arc<str> = Arc::from(display.as_str());
+    let activity = Arc::new(Activity::new());
+    let tail = Arc::new(OutputTail::default());
+    let stdout_task = tokio::spawn(drain_reader(
+        stdout,
+        Stream::Stdout,
+        Arc::clone(&command),
+        Arc::clone(&activity),
+        Arc::clone(&tail),
+        log_tx.clone(),
+    ));
+    let stderr_task = tokio::spawn(drain_reader(
+        stderr,
+        Stream::Stderr,
+        Arc::clone(&command),
+        Arc::clone(&activity),
+        Arc::clone(&tail),
+        log_tx.clone(),
+    ));
 
     // Wait for the child to exit. Because the reader tasks are independently
     // spawned and continuously draining the pipes, the child can never block
-    // on a full pipe buffer — no deadlock possible.
-    let status = child
-        .wait()
-        .await
-        .with_context(|| format!("Failed to wait for: {cmd}"))?;
+    // on a full pipe buffer — no deadlock possible.  Meanwhile the watchdog
+    // checks the time limits once a second.  The child is reaped on a
+    // blocking thread, which also collects what it cost.
+    let mut exit = tokio::task::spawn_blocking(move || reap(child));
+    let mut watchdog = tokio::time::interval(Duration::from_secs(1));
+    let mut stall_notices = 0;
+    // Paused time so far, when the group was stopped.
+    let mut stopped: Option<Duration> = None;
+    let waited = loop {
+        tokio::select! {
+            exit = &mut exit => break Ok(exit),
+            _ = pause.changed() => {}
+            _ = watchdog.tick() => {}
+        }
+        let clock = *pause.borrow_and_update();
+        match (clock.paused(), stopped) {
+            (true, None) => {
+                group.stop();
+                stopped = Some(clock.elapsed());
+            }
+            (false, Some(at)) => {
+                group.resume();
+                // A pause is not silence.
+                activity.postpone(clock.elapsed().saturating_sub(at));
+                stopped = None;
+            }
+            _ => {}
+        }
+        if stopped.is_some() {
+            continue;
+        }
+        let idle = activity.idle();
+        if let Some(limit) = spec.timeout.filter(|&limit| active(&clock) >= limit) {
+            break Err(format!(
+                "Command timed out after {}: {display}",
+                format_duration(limit.as_secs())
+            ));
+        }
+        if let Some(limit) = spec.idle_timeout.filter(|&limit| idle >= limit) {
+            break Err(format!(
+                "Command stalled — no output for {}: {display}",
+                format_duration(limit.as_secs())
+            ));
+        }
+        let notices = idle.as_secs() / STALL_NOTICE.as_secs();
+        if notices != stall_notices {
+            stall_notices = notices;
+            log_tx
+                .send(AppMessage::Stalled {
+                    command: Arc::clone(&command),
+                    silent_for: (notices > 0).then_some(idle),
+                })
+                .ok();
+        }
+    };
+    if stall_notices > 0 {
+        log_tx
+            .send(AppMessage::Stalled {
+                command,
+                silent_for: None,
+            })
+            .ok();
+    }
+    let exit = match waited {
+        Ok(exit) => exit,
+        Err(reason) => {
+            group.kill();
+            let exit = exit.await;
+            group.reaped();
+            // Grandchildren may still hold the pipes open; stop reading them.
+            stdout_task.abort();
+            stderr_task.abort();
+            if let Ok(Ok((_, usage))) = exit {
+                report_usage(usage, active(&pause.borrow()), log_tx);
+            }
+            return Err(CommandError {
+                message: reason,
+                output: tail.take(),
+            }
+            .into());
+        }
+    };
+    group.reaped();
+    let (status, usage) = exit
+        .context("Reaper thread panicked")?
+        .with_context(|| format!("Failed to wait for: {display}"))?;
 
     // Ensure every last byte is flushed before we check the exit code.
     let _ = stdout_task.await;
     let _ = stderr_task.await;
+    report_usage(usage, active(&pause.borrow()), log_tx);
 
     if !status.success() {
         let code = status
             .code()
             .map(|c| c.to_string())
             .unwrap_or_else(|| "signal".to_owned());
-        bail!("Command failed (exit {code}): {cmd}");
+        return Err(CommandError {
+            message: format!("Command failed (exit {code}): {display}"),
+            output: tail.take(),
+        }
+        .into());
     }
 
     Ok(())
 }
 
+/// Log `usage` — with `wall` as its wall time — as one line, and pass it
+/// on for the build's totals.
+fn report_usage(mut usage: ResourceUsage, wall: Duration, tx: &Sender<AppMessage>) {
+    usage.wall_ms = wall.as_millis() as u64;
+    log_msg(tx, &format!("⏱  {}\n", usage.summary()));
+    tx.send(AppMessage::Usage(usage)).ok();
+}
+
 /// Continuously read `reader` in 8 KiB chunks and forward sanitised UTF-8
-/// text to `tx`.  Carriage returns not followed by a newline are replaced
-/// with newlines so that git/cmake progress displays properly.
-async fn drain_reader<R: AsyncReadExt + Unpin>(mut reader: R, tx: Sender<AppMessage>) {
+/// text to `tx` as `stream` output of `command`, keeping a copy in `tail`.
+///
+/// Only whole lines (ending in `\n`, or in a `\r` progress update) are
+/// forwarded, so stdout and stderr never interleave mid-line and a
+/// multi-byte character or escape sequence is never cut in half.  A partial
+/// line is forwarded anyway once the stream has been quiet for
+/// `PARTIAL_LINE_FLUSH`, so prompts and progress bars still show up live.
+async fn drain_reader<R: AsyncReadExt + Unpin>(
+    mut reader: R,
+    stream: Stream,
+    command: Arc<str>,
+    activity: Arc<Activity>,
+    tail: Arc<OutputTail>,
+    tx: Sender<AppMessage>,
+) {
     let mut buf = vec![0u8; 8192];
-    let mut carry = Vec::new(); // bytes from last chunk that ended mid-CR/LF
+    let mut carry = Vec::new(); // bytes read but not yet forwarded
+    let forward = |carry: &mut Vec<u8>, end: usize| {
+        let text = sanitise(&String::from_utf8_lossy(&carry[..end]));
+        carry.drain(..end);
+        if !text.is_empty() {
+            tail.push(&text);
+            tx.send(AppMessage::Output {
+                command: Arc::clone(&command),
+                stream,
+                text,
+            })
+            .ok();
+        }
+    };
 
     loop {
-        let n = match reader.read(&mut buf).await {
+        let read = if carry.is_empty() {
+            reader.read(&mut buf).await
+        } else {
+            match tokio::time::timeout(PARTIAL_LINE_FLUSH, reader.read(&mut buf)).await {
+                Ok(read) => read,
+                Err(_) => {
+                    // Quiet for a while: show the partial line as it stands.
+                    let end = complete_prefix(&carry);
+                    forward(&mut carry, end);
+                    continue;
+                }
+            }
+        };
+        let n = match read {
             Ok(0) | Err(_) => break, // EOF or error — stop reading
             Ok(n) => n,
         };
+        activity.touch();
 
-        // Combine any leftover bytes with the new chunk.
         carry.extend_from_slice(&buf[..n]);
+        let end = if carry.len() > MAX_PARTIAL_LINE {
+            complete_prefix(&carry)
+        } else {
+            line_end(&carry)
+        };
+        forward(&mut carry, end);
+    }
+
+    // Flush any remaining bytes.
+    let end = carry.len();
+    forward(&mut carry, end);
+}
+
+// ─── Failure ──────────────────────────────────────────────────────────────────
+
+/// A command that ran but failed — non-zero exit, or killed for running
+/// too long — with the last `OUTPUT_TAIL` bytes it printed.  Survives
+/// `.context(…)`, so callers further up can still find it in the chain.
+#[derive(Debug)]
+pub struct CommandError {
+    pub message: String,
+    /// Both streams interleaved as they arrived, ANSI colour included.
+    pub output: String,
+}
 
-        // Convert to a lossy UTF-8 string, replacing \r not followed by \n
-        // with \n so the log view shows each progress update on its own line.
-        let text = String::from_utf8_lossy(&carry);
-        let sanitised = sanitise_cr(text.as_ref());
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for CommandError {}
 
-        // If the chunk ends mid-sequence (no trailing newline) we hold the
-        // last incomplete "line" in carry so it isn't split across chunks.
-        // For simplicity we forward everything and reset carry.
-        carry.clear();
+/// The end of a command's output, shared by its two reader tasks.
+#[derive(Default)]
+struct OutputTail(Mutex<String>);
 
-        if !sanitised.is_empty() {
-            tx.send(AppMessage::Log(sanitised)).ok();
+impl OutputTail {
+    fn push(&self, text: &str) {
+        let mut tail = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        tail.push_str(text);
+        // Trim in batches rather than on every chunk.
+        if tail.len() > 2 * OUTPUT_TAIL {
+            let mut cut = tail.len() - OUTPUT_TAIL;
+            while !tail.is_char_boundary(cut) {
+                cut += 1;
+            }
+            tail.drain(..cut);
         }
     }
 
-    // Flush any remaining bytes.
-    if !carry.is_empty() {
-        let text = String::from_utf8_lossy(&carry);
-        let sanitised = sanitise_cr(text.as_ref());
-        if !sanitised.is_empty() {
-            tx.send(AppMessage::Log(sanitised)).ok();
+    fn take(&self) -> String {
+        std::mem::take(&mut *self.0.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+// ─── Resource usage ───────────────────────────────────────────────────────────
+
+/// What a command cost.  CPU times include every descendant it waited for
+/// — the compilers under make, the rustc processes under cargo — and the
+/// peak is the largest resident set of any one of those processes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceUsage {
+    pub wall_ms: u64,
+    pub user_ms: u64,
+    pub sys_ms: u64,
+    pub peak_rss_bytes: u64,
+}
+
+impl ResourceUsage {
+    /// Fold in another command run after this one: times add up, the peak
+    /// is the larger of the two.
+    pub fn add(&mut self, other: &ResourceUsage) {
+        self.wall_ms += other.wall_ms;
+        self.user_ms += other.user_ms;
+        self.sys_ms += other.sys_ms;
+        self.peak_rss_bytes = self.peak_rss_bytes.max(other.peak_rss_bytes);
+    }
+
+    /// e.g. `3m 12s wall · 41m 07s user · 1m 02s sys · peak 1.4 GB`.
+    #[must_use]
+    pub fn summary(&self) -> String {
+        format!(
+            "{} wall · {} user · {} sys · peak {}",
+            format_duration(self.wall_ms / 1_000),
+            format_duration(self.user_ms / 1_000),
+            format_duration(self.sys_ms / 1_000),
+            format_bytes(self.peak_rss_bytes)
+        )
+    }
+}
+
+/// Block until `child` exits and reap it, collecting its CPU time and peak
+/// memory.  `wall_ms` is left for the caller, who knows when it started.
+#[cfg(unix)]
+fn reap(child: std::process::Child) -> std::io::Result<(ExitStatus, ResourceUsage)> {
+    use std::os::unix::process::ExitStatusExt;
+
+    let pid = i32::try_from(child.id()).map_err(std::io::Error::other)?;
+    let mut status = 0;
+    // SAFETY: an all-zero `rusage` is a valid value (plain integers).
+    let mut rusage: libc::rusage = unsafe { std::mem::zeroed() };
+    loop {
+        // SAFETY: both pointers refer to live, writable locals.
+        let ret = unsafe { libc::wait4(pid, &mut status, 0, &mut rusage) };
+        if ret == pid {
+            break;
+        }
+        let err = std::io::Error::last_os_error();
+        if err.kind() != std::io::ErrorKind::Interrupted {
+            return Err(err);
+        }
+    }
+    let millis = |tv: libc::timeval| tv.tv_sec as u64 * 1_000 + tv.tv_usec as u64 / 1_000;
+    // Linux reports the peak in KiB, macOS in bytes.
+    let rss_unit = if cfg!(target_os = "macos") { 1 } else { 1_024 };
+    let usage = ResourceUsage {
+        wall_ms: 0,
+        user_ms: millis(rusage.ru_utime),
+        sys_ms: millis(rusage.ru_stime),
+        peak_rss_bytes: rusage.ru_maxrss as u64 * rss_unit,
+    };
+    Ok((ExitStatus::from_raw(status), usage))
+}
+
+/// Without wait4(2) only the exit status is available.
+#[cfg(not(unix))]
+fn reap(mut child: std::process::Child) -> std::io::Result<(ExitStatus, ResourceUsage)> {
+    child
+        .wait()
+        .map(|status| (status, ResourceUsage::default()))
+}
+
+// ─── Priority ─────────────────────────────────────────────────────────────────
+
+/// How hard commands compete with the rest of the machine.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    #[default]
+    Normal,
+    /// Lowered CPU and I/O priority, so the workstation stays responsive.
+    Background,
+    /// `Background`, and builds start with fewer jobs while the machine is
+    /// already busy.
+    Adaptive,
+}
+
+impl Priority {
+    /// Every priority in combobox order.
+    pub const ALL: [Priority; 3] = [Priority::Normal, Priority::Background, Priority::Adaptive];
+
+    /// Human-readable label, also accepted by `--priority`.
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            Priority::Normal => "Normal",
+            Priority::Background => "Background",
+            Priority::Adaptive => "Adaptive",
         }
     }
+
+    #[must_use]
+    pub fn is_background(self) -> bool {
+        self != Priority::Normal
+    }
+}
+
+/// Start every following command at `priority`.  A running command keeps
+/// the priority it started with.
+pub fn set_priority(priority: Priority) {
+    *PRIORITY.write().unwrap_or_else(|e| e.into_inner()) = priority;
 }
 
-/// Normalize line endings: collapse Windows CRLF (\r\n) → \n, and strip
-/// ANSI escape sequences. Bare \r (carriage return without \n) is passed
-/// through unchanged so that append_log can apply true terminal semantics
+#[must_use]
+pub fn priority() -> Priority {
+    *PRIORITY.read().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Runs in the child between fork and exec, so whatever the command starts
+/// inherits it: nice the process and drop its disk I/O to the lowest
+/// best-effort level (what `nice ionice -c2 -n7` does).  On macOS the
+/// darwin background band additionally throttles disk and network I/O.
+/// Best effort — if the kernel refuses, the command runs at normal priority.
+#[cfg(unix)]
+fn lower_priority() -> std::io::Result<()> {
+    // SAFETY: plain system calls on the calling process; no memory is
+    // touched and nothing here allocates or takes locks.
+    unsafe {
+        libc::setpriority(libc::PRIO_PROCESS, 0, BACKGROUND_NICE);
+        #[cfg(target_os = "macos")]
+        libc::setpriority(libc::PRIO_DARWIN_PROCESS, 0, libc::PRIO_DARWIN_BG);
+        #[cfg(target_os = "linux")]
+        {
+            const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+            const IOPRIO_CLASS_BE: libc::c_int = 2;
+            const IOPRIO_CLASS_SHIFT: libc::c_int = 13;
+            libc::syscall(
+                libc::SYS_ioprio_set,
+                IOPRIO_WHO_PROCESS,
+                0,
+                (IOPRIO_CLASS_BE << IOPRIO_CLASS_SHIFT) | 7,
+            );
+        }
+    }
+    Ok(())
+}
+
+/// One-minute load average, where the platform reports one.
+#[must_use]
+pub fn load_average() -> Option<f64> {
+    #[cfg(unix)]
+    {
+        let mut load = [0.0f64; 1];
+        // SAFETY: `load` has room for the one sample asked for.
+        let samples = unsafe { libc::getloadavg(load.as_mut_ptr(), 1) };
+        (samples == 1).then_some(load[0])
+    }
+    #[cfg(not(unix))]
+    None
+}
+
+// ─── Pause ────────────────────────────────────────────────────────────────────
+
+#[derive(Clone, Copy, Debug, Default)]
+struct PauseClock {
+    /// When the current pause began.
+    since: Option<Instant>,
+    /// Length of every earlier pause.
+    total: Duration,
+}
+
+impl PauseClock {
+    fn paused(&self) -> bool {
+        self.since.is_some()
+    }
+
+    /// Length of every pause so far, including the current one.
+    fn elapsed(&self) -> Duration {
+        self.total + self.since.map_or(Duration::ZERO, |since| since.elapsed())
+    }
+}
+
+/// Suspend (`true`) or continue (`false`) the running command's whole
+/// process group.  While paused, no new command starts either.
+pub fn set_paused(paused: bool) {
+    PAUSE.send_if_modified(|clock| match (paused, clock.since) {
+        (true, None) => {
+            clock.since = Some(Instant::now());
+            true
+        }
+        (false, Some(since)) => {
+            clock.total += since.elapsed();
+            clock.since = None;
+            true
+        }
+        _ => false,
+    });
+}
+
+/// Total time spent paused since the app started; the difference between
+/// two readings is the paused part of the span between them.
+#[must_use]
+pub fn paused_time() -> Duration {
+    PAUSE.borrow().elapsed()
+}
+
+// ─── Watchdog ─────────────────────────────────────────────────────────────────
+
+/// When a command last wrote anything, shared by its two reader tasks.
+struct Activity {
+    started: Instant,
+    /// Milliseconds after `started`.
+    last_output: AtomicU64,
+}
+
+impl Activity {
+    fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            last_output: AtomicU64::new(0),
+        }
+    }
+
+    fn touch(&self) {
+        let now = self.started.elapsed().as_millis() as u64;
+        self.last_output.store(now, Ordering::Relaxed);
+    }
+
+    /// Treat the last output as `by` later than it was.
+    fn postpone(&self, by: Duration) {
+        self.last_output
+            .fetch_add(by.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    /// Time since the last output (or the start).
+    fn idle(&self) -> Duration {
+        let last = Duration::from_millis(self.last_output.load(Ordering::Relaxed));
+        self.started.elapsed().saturating_sub(last)
+    }
+}
+
+/// The child's process group, killed when dropped unless the child has
+/// been reaped — so cancelling the task leaves no orphans behind.
+struct ProcessGroup {
+    pid: u32,
+    running: bool,
+}
+
+impl ProcessGroup {
+    fn new(pid: u32) -> Self {
+        Self { pid, running: true }
+    }
+
+    /// Kill the child and every process in its group.
+    fn kill(&self) {
+        #[cfg(unix)]
+        self.signal(libc::SIGKILL);
+    }
+
+    /// Suspend the whole group, wherever each process is.
+    fn stop(&self) {
+        #[cfg(unix)]
+        self.signal(libc::SIGSTOP);
+    }
+
+    /// Continue a stopped group.
+    fn resume(&self) {
+        #[cfg(unix)]
+        self.signal(libc::SIGCONT);
+    }
+
+    #[cfg(unix)]
+    fn signal(&self, signal: libc::c_int) {
+        if let Ok(pid) = i32::try_from(self.pid) {
+            // SAFETY: kill(2) has no memory-safety preconditions; the group
+            // was created for this child by `process_group(0)`, and the child
+            // has not been reaped, so its pid cannot have been reused.
+            unsafe {
+                libc::kill(-pid, signal);
+            }
+        }
+    }
+
+    /// The child has exited and been reaped; its pid is no longer ours.
+    fn reaped(&mut self) {
+        self.running = false;
+    }
+}
+
+impl Drop for ProcessGroup {
+    fn drop(&mut self) {
+        if self.running {
+            self.kill();
+        }
+    }
+}
+
+/// Length of the whole lines at the start of `bytes`.  A trailing `\r` may
+/// be the first half of `\r\n`, so it waits for the next read.
+fn line_end(bytes: &[u8]) -> usize {
+    if bytes.last() == Some(&b'\n') {
+        return bytes.len();
+    }
+    let body = &bytes[..bytes.len().saturating_sub(1)];
+    body.iter()
+        .rposition(|&b| b == b'\n' || b == b'\r')
+        .map_or(0, |i| i + 1)
+}
+
+/// Length of `bytes` without a UTF-8 character or escape sequence that is
+/// cut off at the end.
+fn complete_prefix(bytes: &[u8]) -> usize {
+    let mut end = bytes.len();
+    // The last character starts at most three bytes from the end.
+    for i in (bytes.len().saturating_sub(3)..bytes.len()).rev() {
+        let width = match bytes[i] {
+            0x80..=0xBF => continue, // continuation byte
+            0xC0..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF7 => 4,
+            _ => 1,
+        };
+        if bytes.len() - i < width {
+            end = i;
+        }
+        break;
+    }
+    end.min(ansi::incomplete_tail(bytes))
+}
+
+/// Normalize line endings: collapse Windows CRLF (\r\n) → \n.  Escape
+/// sequences the log panel cannot show — cursor movement, line erasing,
+/// window titles — are stripped; colour (SGR) sequences are kept for it to
+/// render.  Bare \r (carriage return without \n) is passed through
+/// unchanged so that the log buffer can apply true terminal semantics
 /// (overwrite the current line), keeping cmake/make progress readable
 /// instead of generating hundreds of stacked duplicate lines.
-fn sanitise_cr(s: &str) -> String {
-    // Fast path: nothing to do for pure ASCII with no special bytes.
+fn sanitise(s: &str) -> String {
+    let s = ansi::strip_controls(s);
+    // Fast path: nothing to do without carriage returns.
     if !s.contains('\r') {
-        return s.to_owned();
+        return s.into_owned();
     }
-    // Collapse \r\n → \n; leave bare \r intact for append_log to handle.
+    // Collapse \r\n → \n; leave bare \r intact for the log buffer.
     s.replace("\r\n", "\n")
 }
 
-/// Run a command and capture its trimmed stdout, returning `None` on failure.
-/// Async so callers inside tokio tasks do not block a worker thread.
+// ─── Capturing ────────────────────────────────────────────────────────────────
+
+/// Run `spec` for its output rather than to show it: the command line is
+/// logged to `log_tx`, stdout and stderr are returned whatever the exit
+/// status.  Spawned like `run_command` — own process group, current
+/// priority — and killed, group and all, past the spec's `timeout`.
+pub async fn capture(spec: &CommandSpec, log_tx: &Sender<AppMessage>) -> Result<Output> {
+    log_msg(log_tx, &format!("\n$ {spec}\n"));
+    output(spec).await
+}
+
+/// Run a command and capture its trimmed stdout, returning `None` on failure
+/// or after `PROBE_TIMEOUT`.  Nothing is logged.  Async so callers inside
+/// tokio tasks do not block a worker thread.
 pub async fn probe(cmd: &[&str], env: &HashMap<String, String>) -> Option<String> {
     let (prog, args) = cmd.split_first()?;
-
-    let output = Command::new(prog)
+    let spec = CommandSpec::new(prog, env)
         .args(args)
-        .env_clear()
-        .envs(env)
-        .output()
-        .await
-        .ok()?;
+        .timeout(PROBE_TIMEOUT);
+    let output = output(&spec).await.ok()?;
 
     if !output.status.success() {
         return None;
@@ -166,3 +878,161 @@ pub async fn probe(cmd: &[&str], env: &HashMap<String, String>) -> Option<String
         .map(|s| s.trim().to_owned())
         .filter(|s| !s.is_empty())
 }
+
+/// Spawn `spec`, collect both streams and wait for it within its time limit.
+async fn output(spec: &CommandSpec) -> Result<Output> {
+    let mut child = Command::from(spec.command())
+        .spawn()
+        .with_context(|| format!("Failed to spawn: {spec}"))?;
+    let mut group = ProcessGroup::new(child.id().context("Child exited before it started")?);
+
+    let read_all = |pipe: Option<Box<dyn AsyncRead + Send + Unpin>>| {
+        tokio::spawn(async move {
+            let mut bytes = Vec::new();
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_end(&mut bytes).await;
+            }
+            bytes
+        })
+    };
+    let stdout = read_all(child.stdout.take().map(|p| Box::new(p) as _));
+    let stderr = read_all(child.stderr.take().map(|p| Box::new(p) as _));
+
+    let waited = match spec.timeout {
+        Some(limit) => tokio::time::timeout(limit, child.wait()).await.ok(),
+        None => Some(child.wait().await),
+    };
+    let Some(status) = waited else {
+        group.kill();
+        let _ = child.wait().await;
+        group.reaped();
+        // Grandchildren may still hold the pipes open; stop reading them.
+        stdout.abort();
+        stderr.abort();
+        return Err(CommandError {
+            message: format!(
+                "Command timed out after {}: {spec}",
+                format_duration(spec.timeout.unwrap_or_default().as_secs())
+            ),
+            output: String::new(),
+        }
+        .into());
+    };
+    group.reaped();
+    let status = status.with_context(|| format!("Failed to wait for: {spec}"))?;
+
+    Ok(Output {
+        status,
+        stdout: stdout.await.unwrap_or_default(),
+        stderr: stderr.await.unwrap_or_default(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_end_takes_whole_lines() {
+        assert_eq!(line_end(b""), 0);
+        assert_eq!(line_end(b"partial"), 0);
+        assert_eq!(line_end(b"one\n"), 4);
+        assert_eq!(line_end(b"one\ntwo"), 4);
+        assert_eq!(line_end(b"50%\r75%"), 4);
+    }
+
+    #[test]
+    fn line_end_holds_back_trailing_cr() {
+        assert_eq!(line_end(b"\r"), 0);
+        assert_eq!(line_end(b"progress\r"), 0);
+        assert_eq!(line_end(b"one\ntwo\r"), 4);
+        assert_eq!(line_end(b"50%\r75%\r"), 4);
+    }
+
+    #[test]
+    fn line_end_joins_crlf_split_across_reads() {
+        // First read ends on the `\r`: nothing is forwarded yet.
+        let mut carry = b"done\r".to_vec();
+        assert_eq!(line_end(&carry), 0);
+        // The `\n` arrives with the next read; the line goes out whole.
+        carry.extend_from_slice(b"\nnext");
+        assert_eq!(line_end(&carry), 6);
+        assert_eq!(sanitise(&String::from_utf8_lossy(&carry[..6])), "done\n");
+    }
+
+    #[test]
+    fn complete_prefix_holds_back_split_characters() {
+        for c in ['é', '€', '😀'] {
+            let mut encoded = [0; 4];
+            let bytes = c.encode_utf8(&mut encoded).as_bytes();
+            for cut in 1..bytes.len() {
+                let mut chunk = b"ab".to_vec();
+                chunk.extend_from_slice(&bytes[..cut]);
+                assert_eq!(complete_prefix(&chunk), 2, "{c:?} cut after {cut} bytes");
+            }
+            let mut whole = b"ab".to_vec();
+            whole.extend_from_slice(bytes);
+            assert_eq!(complete_prefix(&whole), whole.len(), "{c:?} whole");
+        }
+    }
+
+    #[test]
+    fn complete_prefix_holds_back_split_escape_sequences() {
+        assert_eq!(complete_prefix(b"red \x1b"), 4);
+        assert_eq!(complete_prefix(b"red \x1b["), 4);
+        assert_eq!(complete_prefix(b"red \x1b[1;3"), 4);
+        assert_eq!(complete_prefix(b"red \x1b[1;31m"), 11);
+        assert_eq!(complete_prefix(b"\x1b[0m plain"), 10);
+    }
+
+    #[test]
+    fn sanitise_keeps_colour_and_bare_carriage_returns() {
+        assert_eq!(sanitise("a\r\nb\r\n"), "a\nb\n");
+        assert_eq!(sanitise("50%\r75%\r"), "50%\r75%\r");
+        assert_eq!(
+            sanitise("\x1b[2K\x1b[1G\x1b[32mok\x1b[0m\r\n"),
+            "\x1b[32mok\x1b[0m\n"
+        );
+        assert_eq!(sanitise("\x1b(B\x1b[mdone\n"), "\x1b[mdone\n");
+    }
+
+    #[cfg(unix)]
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("runtime")
+            .block_on(future)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn output_keeps_both_streams_of_a_failed_command() {
+        let env = std::env::vars().collect();
+        let spec = CommandSpec::new("sh", &env).args(["-c", "echo out; echo err >&2; exit 3"]);
+        let output = block_on(output(&spec)).expect("ran");
+        assert_eq!(output.status.code(), Some(3));
+        assert_eq!(output.stdout, b"out\n");
+        assert_eq!(output.stderr, b"err\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn output_kills_a_command_past_its_timeout() {
+        let env = std::env::vars().collect();
+        let spec = CommandSpec::new("sleep", &env)
+            .arg("30")
+            .timeout(Duration::from_millis(200));
+        let started = Instant::now();
+        let err = block_on(output(&spec)).expect_err("timed out");
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(err.to_string().starts_with("Command timed out"), "{err}");
+    }
+
+    #[test]
+    fn complete_prefix_keeps_complete_text() {
+        assert_eq!(complete_prefix(b""), 0);
+        assert_eq!(complete_prefix(b"ascii"), 5);
+        assert_eq!(complete_prefix("naïve".as_bytes()), 6);
+    }
+}
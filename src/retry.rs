@@ -0,0 +1,323 @@
+// src/retry.rs
+//
+// Retry for network operations — the git clone and the GitHub API calls.
+//
+// `retry`          — run an operation up to `attempts()` times, backing off
+//                    exponentially and logging each retry with its reason.
+// `transient_git`  — is a failed `git clone` worth another try?
+// `transient_http` — is a failed GitHub request worth another try?
+//
+// Only failures known to be transient — timeouts, dropped connections,
+// 5xx responses — are retried.  Anything else (a missing tag, a failed
+// login, an unrecognised error) fails at once: trying again would only
+// fail the same way, more slowly.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::mpsc::Sender;
+use std::time::Duration;
+
+use anyhow::Result;
+use reqwest::StatusCode;
+
+use crate::history::format_duration;
+use crate::messages::{log_msg, AppMessage};
+use crate::process::CommandError;
+
+/// Attempts per network operation unless configured otherwise.
+pub const DEFAULT_ATTEMPTS: u32 = 3;
+/// Wait before the first retry; doubled for each one after.
+const FIRST_BACKOFF: Duration = Duration::from_secs(2);
+/// Longest wait between two attempts.
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Attempts per network operation, for the whole app.
+static ATTEMPTS: AtomicU32 = AtomicU32::new(DEFAULT_ATTEMPTS);
+
+/// Output of a failed `git clone` that means the network let it down, with
+/// the reason logged for the retry.  Checked after `PERMANENT_GIT`, in
+/// order: an HTTP status names the cause better than the `RPC failed` line
+/// git prints with it.
+const TRANSIENT_GIT: &[(&str, &str)] = &[
+    (RATE_LIMITED_GIT, "rate limited"),
+    ("returned error: 5", "server error"),
+    ("command stalled", "no output"),
+    ("could not resolve host", "DNS lookup failed"),
+    ("connection reset", "connection reset"),
+    ("connection timed out", "connection timed out"),
+    ("operation timed out", "connection timed out"),
+    ("failed to connect", "could not connect"),
+    ("couldn't connect to server", "could not connect"),
+    ("early eof", "connection dropped"),
+    ("the remote end hung up unexpectedly", "connection dropped"),
+    ("unexpected disconnect", "connection dropped"),
+    ("rpc failed", "connection dropped"),
+    ("http/2 stream", "connection dropped"),
+    ("gnutls_handshake() failed", "TLS handshake failed"),
+    ("ssl_error_syscall", "TLS connection dropped"),
+];
+
+/// Output of a failed `git clone` that no retry will fix — a tag that does
+/// not exist, or a repository that wants credentials — even when a
+/// transient-looking line appears alongside it.  A clone still running at
+/// its overall time limit is not retried either: another try could block
+/// the build for as long again.  Only going silent (`command stalled`) is.
+const PERMANENT_GIT: &[&str] = &[
+    "command timed out",
+    "not found in upstream origin",
+    "repository not found",
+    "authentication failed",
+    "could not read username",
+    "returned error: 4",
+];
+
+/// A 429 from GitHub over HTTPS: the one 4xx that clears by itself, so it
+/// is retried like `transient_http` retries it, not caught by the
+/// `returned error: 4` in `PERMANENT_GIT`.
+const RATE_LIMITED_GIT: &str = "returned error: 429";
+
+// ─── Configuration ────────────────────────────────────────────────────────────
+
+/// Set how many times each network operation is tried (at least once).
+pub fn set_attempts(attempts: u32) {
+    ATTEMPTS.store(attempts.max(1), Ordering::Relaxed);
+}
+
+#[must_use]
+pub fn attempts() -> u32 {
+    ATTEMPTS.load(Ordering::Relaxed)
+}
+
+// ─── Retry loop ───────────────────────────────────────────────────────────────
+
+/// Run `op` until it succeeds, fails permanently or has been tried
+/// `attempts()` times.  `transient` names the reason a failure is worth
+/// retrying, or returns `None` for one that is not.  Each retry is logged
+/// to `log_tx` as `what` failed, why and when it will be tried again.
+pub async fn retry<T, F, Fut>(
+    what: &str,
+    log_tx: &Sender<AppMessage>,
+    transient: impl Fn(&anyhow::Error) -> Option<String>,
+    mut op: F,
+) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let attempts = attempts();
+    let mut backoff = FIRST_BACKOFF;
+    let mut attempt = 1;
+    loop {
+        let err = match op().await {
+            Ok(value) => return Ok(value),
+            Err(err) => err,
+        };
+        let reason = match transient(&err) {
+            Some(reason) if attempt < attempts => reason,
+            _ => return Err(err),
+        };
+        log_msg(
+            log_tx,
+            &format!(
+                "\n🔁 {what} failed ({reason}) — retrying in {} (attempt {} of {attempts})…\n",
+                format_duration(backoff.as_secs()),
+                attempt + 1
+            ),
+        );
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+        attempt += 1;
+    }
+}
+
+// ─── Transient errors ─────────────────────────────────────────────────────────
+
+/// Why a failed git network command is worth retrying, judged by its error
+/// and the output it left in a `CommandError`; `None` if it is not.
+#[must_use]
+pub fn transient_git(err: &anyhow::Error) -> Option<String> {
+    let output = err
+        .chain()
+        .find_map(|e| e.downcast_ref::<CommandError>())
+        .map(|e| e.output.to_lowercase())
+        .unwrap_or_default();
+    let text = format!("{err:#}").to_lowercase() + "\n" + &output;
+
+    let unlimited = text.replace(RATE_LIMITED_GIT, "");
+    if PERMANENT_GIT
+        .iter()
+        .any(|signature| unlimited.contains(signature))
+    {
+        return None;
+    }
+    TRANSIENT_GIT
+        .iter()
+        .find(|(signature, _)| text.contains(signature))
+        .map(|(_, reason)| (*reason).to_owned())
+}
+
+/// Why a failed HTTP request is worth retrying — a timeout, a connection
+/// that failed or dropped, a 5xx response or a 429 (GitHub's secondary
+/// rate limit, which clears within a minute); `None` if it is not (e.g. a
+/// 404, a malformed body, or the primary rate limit's 403).
+#[must_use]
+pub fn transient_http(err: &anyhow::Error) -> Option<String> {
+    let err = err
+        .chain()
+        .find_map(|e| e.downcast_ref::<reqwest::Error>())?;
+    if let Some(status) = err.status() {
+        let retry = status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS;
+        return retry.then(|| format!("HTTP {status}"));
+    }
+    let reason = if err.is_timeout() {
+        "timed out"
+    } else if err.is_connect() {
+        "could not connect"
+    } else if err.is_request() || err.is_body() {
+        "connection dropped"
+    } else {
+        return None;
+    };
+    Some(reason.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A failed `git clone` as `run_command` reports it, with `output` as
+    /// what git printed.
+    fn clone_failure(message: &str, output: &str) -> anyhow::Error {
+        anyhow::Error::new(CommandError {
+            message: message.to_owned(),
+            output: output.to_owned(),
+        })
+        .context("git clone failed")
+    }
+
+    #[test]
+    fn git_failures_are_classified() {
+        const EXIT: &str = "Command failed (exit 128): git clone --depth 1 --branch v99";
+        let cases: &[(&str, &str, Option<&str>)] = &[
+            (
+                EXIT,
+                "Cloning into 'bitcoin-99'...\n\
+                 warning: Could not find remote branch v99 to clone.\n\
+                 fatal: Remote branch v99 not found in upstream origin\n",
+                None,
+            ),
+            (
+                EXIT,
+                "remote: Repository not found.\n\
+                 fatal: repository 'https://github.com/bitcoin/bitcion.git/' not found\n",
+                None,
+            ),
+            (
+                EXIT,
+                "fatal: could not read Username for 'https://github.com': \
+                 terminal prompts disabled\n",
+                None,
+            ),
+            (
+                EXIT,
+                "error: RPC failed; curl 56 GnuTLS recv error (-9): \
+                 Error decoding the received TLS packet.\n\
+                 fetch-pack: unexpected disconnect while reading sideband packet\n\
+                 fatal: early EOF\n\
+                 fatal: fetch-pack: invalid index-pack output\n",
+                Some("connection dropped"),
+            ),
+            (
+                EXIT,
+                "Receiving objects:  41% (120000/290000), 80.00 MiB | 2.00 MiB/s\n\
+                 fatal: early EOF\n",
+                Some("connection dropped"),
+            ),
+            (
+                EXIT,
+                "fatal: unable to access 'https://github.com/bitcoin/bitcoin.git/': \
+                 Could not resolve host: github.com\n",
+                Some("DNS lookup failed"),
+            ),
+            (
+                EXIT,
+                "error: RPC failed; HTTP 502 curl 22 \
+                 The requested URL returned error: 502\n",
+                Some("server error"),
+            ),
+            (
+                EXIT,
+                "error: RPC failed; HTTP 429 curl 22 \
+                 The requested URL returned error: 429\n",
+                Some("rate limited"),
+            ),
+            (
+                EXIT,
+                "error: RPC failed; HTTP 403 curl 22 \
+                 The requested URL returned error: 403\n",
+                None,
+            ),
+            (
+                "Command stalled — no output for 10m 00s: git clone --depth 1",
+                "Receiving objects:  12% (34800/290000)\r",
+                Some("no output"),
+            ),
+            (
+                "Command timed out after 30m 00s: git clone --depth 1",
+                "Receiving objects:  97% (281300/290000)\r",
+                None,
+            ),
+        ];
+        for (message, output, expected) in cases {
+            let err = clone_failure(message, output);
+            assert_eq!(
+                transient_git(&err).as_deref(),
+                *expected,
+                "{message}\n{output}"
+            );
+        }
+    }
+
+    #[test]
+    fn git_errors_without_output_are_permanent() {
+        let err = anyhow::anyhow!("Failed to spawn: git clone").context("git clone failed");
+        assert_eq!(transient_git(&err), None);
+    }
+
+    /// The error `error_for_status` gives for a `status` response.
+    fn http_failure(status: u16) -> anyhow::Error {
+        let response = http::Response::builder()
+            .status(status)
+            .body(Vec::new())
+            .expect("valid response");
+        let err = reqwest::Response::from(response)
+            .error_for_status()
+            .expect_err("error status");
+        anyhow::Error::new(err).context("GitHub API returned error status")
+    }
+
+    #[test]
+    fn http_statuses_are_classified() {
+        assert_eq!(transient_http(&http_failure(404)), None);
+        assert_eq!(transient_http(&http_failure(403)), None);
+        assert_eq!(transient_http(&http_failure(401)), None);
+        assert_eq!(
+            transient_http(&http_failure(502)).as_deref(),
+            Some("HTTP 502 Bad Gateway")
+        );
+        assert_eq!(
+            transient_http(&http_failure(503)).as_deref(),
+            Some("HTTP 503 Service Unavailable")
+        );
+        assert_eq!(
+            transient_http(&http_failure(429)).as_deref(),
+            Some("HTTP 429 Too Many Requests")
+        );
+    }
+
+    #[test]
+    fn non_http_errors_are_not_retried_as_http() {
+        let err = anyhow::anyhow!("Failed to parse Bitcoin Core release JSON");
+        assert_eq!(transient_http(&err), None);
+    }
+}
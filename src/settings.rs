@@ -0,0 +1,111 @@
+// src/settings.rs
+//
+// User settings persisted between launches as JSON in the platform config
+// directory.  Every field is optional on disk (`#[serde(default)]`), so
+// files written by older or newer builds still load; a file that cannot be
+// parsed at all is reported and replaced by defaults.
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::options::{BuildOptions, Target};
+use crate::process::Priority;
+
+const SETTINGS_FILE: &str = "settings.json";
+
+// ─── Config directory ─────────────────────────────────────────────────────────
+
+/// BitForge's configuration directory:
+/// `~/Library/Application Support/BitForge` on macOS, otherwise
+/// `$XDG_CONFIG_HOME/bitforge` (default `~/.config/bitforge`).
+#[must_use]
+pub fn config_dir() -> Option<PathBuf> {
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+
+    if cfg!(target_os = "macos") {
+        return home.map(|h| h.join("Library/Application Support/BitForge"));
+    }
+
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| home.map(|h| h.join(".config")))
+        .map(|p| p.join("bitforge"))
+}
+
+/// Default build directory: `~/Downloads/bitcoin_builds`.
+#[must_use]
+pub fn default_build_dir() -> String {
+    std::env::var_os("HOME")
+        .map(|h| {
+            PathBuf::from(h)
+                .join("Downloads/bitcoin_builds")
+                .to_string_lossy()
+                .into_owned()
+        })
+        .unwrap_or_else(|| "/tmp/bitcoin_builds".to_owned())
+}
+
+// ─── Settings ─────────────────────────────────────────────────────────────────
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub target: Target,
+    /// `None` → `max_cores - 1`.
+    pub cores: Option<usize>,
+    /// `None` → `~/Downloads/bitcoin_builds`.
+    pub build_dir: Option<String>,
+    /// Last selected tags; re-selected if GitHub still lists them.
+    pub bitcoin_version: Option<String>,
+    pub electrs_version: Option<String>,
+    pub options: BuildOptions,
+    /// Inner window size in points.
+    pub window_size: Option<[f32; 2]>,
+    /// Keep ANSI colour codes in per-build log files instead of stripping
+    /// them to plain text.
+    pub keep_log_colors: bool,
+    /// CPU and I/O priority of build commands.
+    pub priority: Priority,
+    /// Tries for each clone or GitHub request; `None` → 3.
+    pub network_attempts: Option<u32>,
+    /// Homebrew to use — its `brew` binary or prefix; `None` → search the
+    /// usual places.  `BITFORGE_BREW` overrides it.
+    pub brew_path: Option<String>,
+}
+
+impl Settings {
+    /// Load settings.  A missing file yields defaults; an unreadable or
+    /// malformed one is an error the caller may report before falling back.
+    pub fn load() -> Result<Self> {
+        let Some(path) = settings_path() else {
+            return Ok(Self::default());
+        };
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+        serde_json::from_str(&text).with_context(|| format!("Malformed settings in {}", path.display()))
+    }
+
+    /// Write settings atomically (temp file + rename).
+    pub fn save(&self) -> Result<()> {
+        let path = settings_path().context("No home directory to store settings in")?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let json = serde_json::to_string_pretty(self).context("Failed to serialise settings")?;
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, json).with_context(|| format!("Failed to write {}", tmp.display()))?;
+        std::fs::rename(&tmp, &path)
+            .with_context(|| format!("Failed to replace {}", path.display()))
+    }
+}
+
+fn settings_path() -> Option<PathBuf> {
+    config_dir().map(|d| d.join(SETTINGS_FILE))
+}
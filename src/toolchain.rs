@@ -0,0 +1,138 @@
+// src/toolchain.rs
+//
+// rustup-managed Rust toolchains for Electrs builds.
+//
+// Homebrew's `rust` formula cannot select versions and shadows rustup's
+// proxies, so rustup is preferred whenever it is installed.  A toolchain
+// for a build comes from (in order): the user's pin, the checkout's
+// `rust-toolchain.toml` / `rust-toolchain` file, or rustup's default.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::mpsc::Sender;
+
+use anyhow::{bail, Context, Result};
+
+use crate::messages::{log_msg, AppMessage};
+use crate::process::{probe, run_command, CommandSpec};
+
+/// Where the toolchain for a build came from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ToolchainSource {
+    /// Pinned by the user in the build options.
+    Pinned,
+    /// Read from the Electrs checkout's toolchain file.
+    ToolchainFile,
+}
+
+impl ToolchainSource {
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            ToolchainSource::Pinned => "pinned",
+            ToolchainSource::ToolchainFile => "from rust-toolchain file",
+        }
+    }
+}
+
+/// Return rustup's version string if rustup is on `PATH`.
+pub async fn rustup_version(env: &HashMap<String, String>) -> Option<String> {
+    probe(&["rustup", "--version"], env).await
+}
+
+/// Pick the toolchain for a build in `src_dir`: the pin wins, then the
+/// checkout's toolchain file.  `None` means "use rustup's default".
+pub async fn resolve_toolchain(
+    pinned: Option<&str>,
+    src_dir: &Path,
+) -> Option<(String, ToolchainSource)> {
+    if let Some(pin) = pinned.map(str::trim).filter(|s| !s.is_empty()) {
+        return Some((pin.to_owned(), ToolchainSource::Pinned));
+    }
+    read_toolchain_file(src_dir)
+        .await
+        .map(|channel| (channel, ToolchainSource::ToolchainFile))
+}
+
+/// Read the channel from `rust-toolchain.toml`, or from the legacy
+/// `rust-toolchain` file which may be either TOML or a bare channel name.
+async fn read_toolchain_file(src_dir: &Path) -> Option<String> {
+    for name in ["rust-toolchain.toml", "rust-toolchain"] {
+        let Ok(text) = tokio::fs::read_to_string(src_dir.join(name)).await else {
+            continue;
+        };
+        let channel = text
+            .lines()
+            .map(str::trim)
+            .find_map(|line| {
+                let value = line.strip_prefix("channel")?.trim_start().strip_prefix('=')?;
+                Some(value.trim().trim_matches(['"', '\'']).to_owned())
+            })
+            .or_else(|| {
+                // Legacy format: the whole file is just the channel name.
+                let bare = text.trim();
+                (!bare.is_empty() && !bare.contains(['\n', '[', '='])).then(|| bare.to_owned())
+            });
+        if let Some(channel) = channel.filter(|c| !c.is_empty()) {
+            return Some(channel);
+        }
+    }
+    None
+}
+
+/// Reject toolchain names that could not possibly be valid, since they end
+/// up on a command line.
+pub fn validate_toolchain(name: &str) -> Result<()> {
+    if !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+    {
+        Ok(())
+    } else {
+        bail!("Toolchain name contains unexpected characters: {name:?}")
+    }
+}
+
+/// Install `toolchain` through rustup if it is not installed yet.
+pub async fn ensure_toolchain(
+    toolchain: &str,
+    env: &HashMap<String, String>,
+    tx: &Sender<AppMessage>,
+) -> Result<()> {
+    validate_toolchain(toolchain)?;
+
+    if toolchain_installed(toolchain, env).await {
+        log_msg(tx, &format!("✓ Toolchain {toolchain} is installed\n"));
+        return Ok(());
+    }
+
+    log_msg(tx, &format!("📦 Installing Rust toolchain {toolchain}...\n"));
+    run_command(&install_toolchain_command(toolchain, env), tx)
+        .await
+        .with_context(|| format!("Failed to install Rust toolchain {toolchain}"))
+}
+
+/// Whether rustup already has `toolchain` (a bare channel such as `1.80.0`
+/// matches its host-qualified name).
+pub async fn toolchain_installed(toolchain: &str, env: &HashMap<String, String>) -> bool {
+    let installed = probe(&["rustup", "toolchain", "list"], env)
+        .await
+        .unwrap_or_default();
+    installed.lines().any(|line| {
+        let name = line.split_whitespace().next().unwrap_or_default();
+        name == toolchain || name.starts_with(&format!("{toolchain}-"))
+    })
+}
+
+/// The rustup command `ensure_toolchain` installs `toolchain` with.
+#[must_use]
+pub fn install_toolchain_command(toolchain: &str, env: &HashMap<String, String>) -> CommandSpec {
+    CommandSpec::new("rustup", env).args([
+        "toolchain",
+        "install",
+        toolchain,
+        "--profile",
+        "minimal",
+    ])
+}
@@ -0,0 +1,744 @@
+// src/tui.rs
+//
+// Full-screen terminal interface (`bitcoin-compiler tui`) for SSH sessions
+// on headless build machines.
+//
+// It is a third front-end over the same plumbing as `BitForgeApp`: the
+// background tasks are spawned unchanged and talk to this module through
+// the usual `AppMessage` and `ConfirmRequest` channels.  Only the
+// rendering differs — ratatui widgets instead of egui.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::mpsc::{Receiver, Sender};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Gauge, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use tokio::runtime::Runtime;
+use tokio::task::JoinHandle;
+
+use crate::ansi;
+use crate::compiler::compile_selection;
+use crate::deps::check_dependencies_task;
+use crate::github::{fetch_bitcoin_versions, fetch_electrs_versions};
+use crate::history::format_duration;
+use crate::log_buffer::LogBuffer;
+use crate::log_view::{classify, Severity};
+use crate::messages::{log_msg, AppMessage, ConfirmItem, ConfirmRequest, Stream};
+use crate::options::{BuildOptions, BuildSelection, Target};
+use crate::process;
+
+/// Lines of log history kept for scrolling.
+const MAX_LOG_LINES: usize = 10_000;
+/// How long to wait for input before redrawing with new messages.
+const TICK: Duration = Duration::from_millis(50);
+/// Longest wait on quitting for an aborted task to drop its future.
+const ABORT_TIMEOUT: Duration = Duration::from_secs(5);
+
+// ─── Launch parameters ────────────────────────────────────────────────────────
+
+/// Initial state, taken from the command line and saved settings.
+pub struct TuiConfig {
+    pub target: Target,
+    pub options: BuildOptions,
+    pub cores: usize,
+    pub max_cores: usize,
+    pub build_dir: PathBuf,
+    /// Tags to pre-select once the version lists arrive, if still listed.
+    pub bitcoin_version: Option<String>,
+    pub electrs_version: Option<String>,
+    pub brew: Option<String>,
+    pub env: HashMap<String, String>,
+    /// Keep ANSI colour in build log files.
+    pub log_colors: bool,
+}
+
+// ─── State ────────────────────────────────────────────────────────────────────
+
+/// Rows of the settings form, in display order.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Target,
+    Bitcoin,
+    Electrs,
+    Jobs,
+    Wallet,
+    Zmq,
+}
+
+const FIELDS: [Field; 6] = [
+    Field::Target,
+    Field::Bitcoin,
+    Field::Electrs,
+    Field::Jobs,
+    Field::Wallet,
+    Field::Zmq,
+];
+
+enum Modal {
+    Alert {
+        title: String,
+        message: String,
+        is_error: bool,
+    },
+    Confirm {
+        title: String,
+        message: String,
+        items: Vec<ConfirmItem>,
+        cursor: usize,
+        response_tx: tokio::sync::oneshot::Sender<Option<Vec<bool>>>,
+    },
+}
+
+struct Tui<'a> {
+    cfg: TuiConfig,
+    runtime: &'a Runtime,
+
+    bitcoin_versions: Vec<String>,
+    bitcoin_idx: usize,
+    electrs_versions: Vec<String>,
+    electrs_idx: usize,
+    field: usize,
+
+    log: LogBuffer,
+    /// Lines scrolled up from the bottom; 0 follows the tail.
+    scroll: usize,
+    progress: f32,
+    /// The running command has printed nothing for this long.
+    stall: Option<Duration>,
+    /// The running task's commands are suspended.
+    paused: bool,
+    task: Option<JoinHandle<()>>,
+    modal: Option<Modal>,
+    /// Set by the first `q` while a task runs; the second one aborts.
+    quit_armed: bool,
+    quit: bool,
+
+    msg_tx: Sender<AppMessage>,
+    msg_rx: Receiver<AppMessage>,
+    confirm_tx: Sender<ConfirmRequest>,
+    confirm_rx: Receiver<ConfirmRequest>,
+}
+
+// ─── Entry point ──────────────────────────────────────────────────────────────
+
+/// Run the terminal interface until the user quits.
+pub fn run(cfg: TuiConfig, runtime: &Runtime) -> Result<()> {
+    let (msg_tx, msg_rx) = std::sync::mpsc::channel::<AppMessage>();
+    let (confirm_tx, confirm_rx) = std::sync::mpsc::channel::<ConfirmRequest>();
+
+    let mut tui = Tui {
+        cfg,
+        runtime,
+        bitcoin_versions: Vec::new(),
+        bitcoin_idx: 0,
+        electrs_versions: Vec::new(),
+        electrs_idx: 0,
+        field: 0,
+        log: LogBuffer::new(MAX_LOG_LINES),
+        scroll: 0,
+        progress: 0.0,
+        stall: None,
+        paused: false,
+        task: None,
+        modal: None,
+        quit_armed: false,
+        quit: false,
+        msg_tx,
+        msg_rx,
+        confirm_tx,
+        confirm_rx,
+    };
+    tui.append_log("BitForge — terminal interface\n");
+    tui.append_log("d: check dependencies   b: build   r: refresh versions   q: quit\n");
+    tui.spawn_refresh_versions();
+
+    // ratatui::init installs a panic hook that restores the terminal.
+    let mut terminal = ratatui::init();
+    let result = tui.event_loop(&mut terminal);
+    ratatui::restore();
+
+    // Dropping the task's future kills its child processes' groups; wait
+    // until it has been dropped.
+    if let Some(task) = tui.task.take() {
+        task.abort();
+        runtime.block_on(async {
+            let _ = tokio::time::timeout(ABORT_TIMEOUT, task).await;
+        });
+    }
+    result
+}
+
+impl Tui<'_> {
+    fn event_loop(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        while !self.quit {
+            self.drain_messages();
+            terminal
+                .draw(|frame| self.render(frame))
+                .context("Failed to draw terminal UI")?;
+
+            if event::poll(TICK).context("Failed to poll terminal events")? {
+                if let Event::Key(key) = event::read().context("Failed to read terminal event")? {
+                    if key.kind == KeyEventKind::Press {
+                        self.on_key(key);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // ─── Messages ─────────────────────────────────────────────────────────────
+
+    fn drain_messages(&mut self) {
+        while let Ok(msg) = self.msg_rx.try_recv() {
+            match msg {
+                AppMessage::Log(s) => self.append_log(&s),
+                AppMessage::Output {
+                    command,
+                    stream,
+                    text,
+                } => {
+                    let stderr_of = (stream == Stream::Stderr).then_some(command);
+                    self.log.push(&text, stderr_of.as_ref());
+                }
+                AppMessage::Progress(v) => self.progress = v.clamp(0.0, 1.0),
+                AppMessage::Stalled { silent_for, .. } => self.stall = silent_for,
+                AppMessage::BitcoinVersionsLoaded(v) => {
+                    self.bitcoin_idx = preferred_index(&v, self.cfg.bitcoin_version.as_deref());
+                    self.bitcoin_versions = v;
+                }
+                AppMessage::ElectrsVersionsLoaded(v) => {
+                    self.electrs_idx = preferred_index(&v, self.cfg.electrs_version.as_deref());
+                    self.electrs_versions = v;
+                }
+                AppMessage::InstalledPackages(_)
+                | AppMessage::StageStarted(_)
+                | AppMessage::BuildFinished { .. }
+                | AppMessage::LogFile(_)
+                | AppMessage::Usage(_)
+                | AppMessage::BuildRecorded(_) => {}
+                AppMessage::ShowDialog {
+                    title,
+                    message,
+                    is_error,
+                } => {
+                    self.modal = Some(Modal::Alert {
+                        title,
+                        message,
+                        is_error,
+                    });
+                }
+                AppMessage::BuildFailed {
+                    mut message,
+                    diagnosis,
+                } => {
+                    if let Some(diagnosis) = diagnosis {
+                        message = format!("{message}\n\n{}", diagnosis.report());
+                    }
+                    self.modal = Some(Modal::Alert {
+                        title: "Compilation Failed".into(),
+                        message,
+                        is_error: true,
+                    });
+                }
+                AppMessage::TaskDone => {
+                    self.task = None;
+                    self.progress = 0.0;
+                    self.stall = None;
+                    self.quit_armed = false;
+                    if self.paused {
+                        self.paused = false;
+                        process::set_paused(false);
+                    }
+                }
+            }
+        }
+
+        if self.modal.is_none() {
+            if let Ok(req) = self.confirm_rx.try_recv() {
+                self.modal = Some(Modal::Confirm {
+                    title: req.title,
+                    message: req.message,
+                    items: req.items,
+                    cursor: 0,
+                    response_tx: req.response_tx,
+                });
+            }
+        }
+    }
+
+    /// Append with terminal semantics: `\r` rewinds the current line.
+    fn append_log(&mut self, text: &str) {
+        self.log.push(text, None);
+    }
+
+    // ─── Tasks ────────────────────────────────────────────────────────────────
+
+    fn is_busy(&self) -> bool {
+        self.task.is_some()
+    }
+
+    /// Suspend the running task's commands, or continue them.
+    fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        process::set_paused(self.paused);
+        self.append_log(if self.paused {
+            "\n⏸  Paused — press p to resume.\n"
+        } else {
+            "▶  Resumed.\n"
+        });
+    }
+
+    fn selection(&self) -> BuildSelection {
+        let pick = |list: &[String], idx: usize| list.get(idx).cloned().unwrap_or_default();
+        BuildSelection {
+            target: self.cfg.target,
+            bitcoin_version: pick(&self.bitcoin_versions, self.bitcoin_idx),
+            electrs_version: pick(&self.electrs_versions, self.electrs_idx),
+            options: self.cfg.options.clone(),
+        }
+    }
+
+    fn spawn_refresh_versions(&self) {
+        let tx = self.msg_tx.clone();
+        self.runtime.spawn(async move {
+            match fetch_bitcoin_versions(&tx).await {
+                Ok(v) => {
+                    tx.send(AppMessage::BitcoinVersionsLoaded(v)).ok();
+                }
+                Err(e) => log_msg(&tx, &format!("⚠️  Could not fetch Bitcoin versions: {e}\n")),
+            }
+            match fetch_electrs_versions(&tx).await {
+                Ok(v) => {
+                    tx.send(AppMessage::ElectrsVersionsLoaded(v)).ok();
+                }
+                Err(e) => log_msg(&tx, &format!("⚠️  Could not fetch Electrs versions: {e}\n")),
+            }
+        });
+    }
+
+    fn spawn_check_deps(&mut self) {
+        let Some(brew) = self.cfg.brew.clone() else {
+            self.modal = Some(Modal::Alert {
+                title: "Homebrew Not Found".into(),
+                message: "Homebrew is required. Install it from https://brew.sh \
+                          or set BITFORGE_BREW."
+                    .into(),
+                is_error: true,
+            });
+            return;
+        };
+        let env = self.cfg.env.clone();
+        let selection = self.selection();
+        let tx = self.msg_tx.clone();
+        let confirm_tx = self.confirm_tx.clone();
+
+        self.append_log("\n>>> Starting dependency check...\n");
+        let handle = self.runtime.spawn(async move {
+            if let Err(e) = check_dependencies_task(brew, env, selection, tx.clone(), confirm_tx).await
+            {
+                log_msg(&tx, &format!("❌ Dependency check failed: {e}\n"));
+            }
+            tx.send(AppMessage::TaskDone).ok();
+        });
+        self.task = Some(handle);
+    }
+
+    fn spawn_compile(&mut self) {
+        let selection = self.selection();
+        let missing = (selection.target.includes_bitcoin() && selection.bitcoin_version.is_empty())
+            || (selection.target.includes_electrs() && selection.electrs_version.is_empty());
+        if missing {
+            self.modal = Some(Modal::Alert {
+                title: "Not Ready".into(),
+                message: "Versions are still loading. Press r to refresh.".into(),
+                is_error: true,
+            });
+            return;
+        }
+
+        let build_dir = self.cfg.build_dir.clone();
+        let cores = self.cfg.cores;
+        let env = self.cfg.env.clone();
+        let log_colors = self.cfg.log_colors;
+        let tx = self.msg_tx.clone();
+
+        self.progress = 0.0;
+        self.scroll = 0;
+        let handle = self.runtime.spawn(async move {
+            let _ = compile_selection(&selection, &build_dir, cores, &env, log_colors, &tx).await;
+            tx.send(AppMessage::TaskDone).ok();
+        });
+        self.task = Some(handle);
+    }
+
+    // ─── Input ────────────────────────────────────────────────────────────────
+
+    fn on_key(&mut self, key: KeyEvent) {
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            self.quit = true;
+            return;
+        }
+        if self.modal.is_some() {
+            self.on_modal_key(key.code);
+            return;
+        }
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => {
+                if !self.is_busy() || self.quit_armed {
+                    self.quit = true;
+                } else {
+                    self.quit_armed = true;
+                    self.append_log("\n⚠️  A task is running. Press q again to abort it and quit.\n");
+                }
+            }
+            KeyCode::Char('d') if !self.is_busy() => self.spawn_check_deps(),
+            KeyCode::Char('b') if !self.is_busy() => self.spawn_compile(),
+            KeyCode::Char('r') => self.spawn_refresh_versions(),
+            KeyCode::Char('p') if self.is_busy() => self.toggle_pause(),
+            KeyCode::Up => self.field = self.field.saturating_sub(1),
+            KeyCode::Down => self.field = (self.field + 1).min(FIELDS.len() - 1),
+            KeyCode::Left if !self.is_busy() => self.adjust(-1),
+            KeyCode::Right | KeyCode::Char(' ') if !self.is_busy() => self.adjust(1),
+            KeyCode::PageUp => self.scroll = (self.scroll + 10).min(self.log.line_count()),
+            KeyCode::PageDown => self.scroll = self.scroll.saturating_sub(10),
+            KeyCode::End => self.scroll = 0,
+            _ => {}
+        }
+    }
+
+    /// Change the focused form field by one step.
+    fn adjust(&mut self, delta: isize) {
+        let step = |idx: usize, len: usize| -> usize {
+            if len == 0 {
+                0
+            } else {
+                (idx as isize + delta).rem_euclid(len as isize) as usize
+            }
+        };
+        match FIELDS[self.field] {
+            Field::Target => {
+                let i = Target::ALL.iter().position(|t| *t == self.cfg.target).unwrap_or(0);
+                self.cfg.target = Target::ALL[step(i, Target::ALL.len())];
+            }
+            Field::Bitcoin => {
+                self.bitcoin_idx = step(self.bitcoin_idx, self.bitcoin_versions.len());
+                self.cfg.bitcoin_version = self.bitcoin_versions.get(self.bitcoin_idx).cloned();
+            }
+            Field::Electrs => {
+                self.electrs_idx = step(self.electrs_idx, self.electrs_versions.len());
+                self.cfg.electrs_version = self.electrs_versions.get(self.electrs_idx).cloned();
+            }
+            Field::Jobs => {
+                self.cfg.cores = self
+                    .cfg
+                    .cores
+                    .saturating_add_signed(delta)
+                    .clamp(1, self.cfg.max_cores);
+            }
+            Field::Wallet => self.cfg.options.wallet = !self.cfg.options.wallet,
+            Field::Zmq => self.cfg.options.zmq = !self.cfg.options.zmq,
+        }
+    }
+
+    fn on_modal_key(&mut self, code: KeyCode) {
+        let answer = match self.modal.as_mut() {
+            Some(Modal::Alert { .. }) => {
+                if matches!(code, KeyCode::Enter | KeyCode::Esc | KeyCode::Char(' ')) {
+                    self.modal = None;
+                }
+                return;
+            }
+            Some(Modal::Confirm { items, cursor, .. }) => match code {
+                KeyCode::Up => {
+                    *cursor = cursor.saturating_sub(1);
+                    None
+                }
+                KeyCode::Down => {
+                    *cursor = (*cursor + 1).min(items.len().saturating_sub(1));
+                    None
+                }
+                KeyCode::Char(' ') => {
+                    if let Some(item) = items.get_mut(*cursor) {
+                        item.checked = !item.checked;
+                    }
+                    None
+                }
+                KeyCode::Char('y') | KeyCode::Enter => Some(true),
+                KeyCode::Char('n') | KeyCode::Esc => Some(false),
+                _ => None,
+            },
+            None => return,
+        };
+
+        if let Some(yes) = answer {
+            if let Some(Modal::Confirm {
+                items, response_tx, ..
+            }) = self.modal.take()
+            {
+                let reply = yes.then(|| items.iter().map(|item| item.checked).collect());
+                response_tx.send(reply).ok();
+            }
+        }
+    }
+
+    // ─── Rendering ────────────────────────────────────────────────────────────
+
+    fn render(&self, frame: &mut Frame) {
+        let [form_area, gauge_area, log_area, help_area] = Layout::vertical([
+            Constraint::Length(FIELDS.len() as u16 + 3),
+            Constraint::Length(3),
+            Constraint::Min(5),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        self.render_form(frame, form_area);
+        self.render_progress(frame, gauge_area);
+        self.render_log(frame, log_area);
+
+        let help = if self.is_busy() {
+            "running…   p: pause/resume   PgUp/PgDn: scroll log   End: follow   q: abort & quit"
+        } else {
+            "↑/↓: field   ←/→/space: change   d: deps   b: build   r: refresh   PgUp/PgDn: scroll   q: quit"
+        };
+        frame.render_widget(
+            Paragraph::new(help).style(Style::default().fg(Color::DarkGray)),
+            help_area,
+        );
+
+        if let Some(modal) = &self.modal {
+            render_modal(frame, modal);
+        }
+    }
+
+    fn render_form(&self, frame: &mut Frame, area: Rect) {
+        let version = |list: &[String], idx: usize| {
+            list.get(idx).cloned().unwrap_or_else(|| "Loading...".to_owned())
+        };
+        let tick = |on: bool| if on { "[x]" } else { "[ ]" };
+        let target = self.cfg.target;
+
+        let mut lines: Vec<Line> = FIELDS
+            .iter()
+            .enumerate()
+            .map(|(i, field)| {
+                let (label, value, enabled) = match field {
+                    Field::Target => ("Target", target.label().to_owned(), true),
+                    Field::Bitcoin => (
+                        "Bitcoin Core",
+                        version(&self.bitcoin_versions, self.bitcoin_idx),
+                        target.includes_bitcoin(),
+                    ),
+                    Field::Electrs => (
+                        "Electrs",
+                        version(&self.electrs_versions, self.electrs_idx),
+                        target.includes_electrs(),
+                    ),
+                    Field::Jobs => (
+                        "CPU Cores",
+                        format!("{} of {}", self.cfg.cores, self.cfg.max_cores),
+                        true,
+                    ),
+                    Field::Wallet => (
+                        "Wallet",
+                        tick(self.cfg.options.wallet).to_owned(),
+                        target.includes_bitcoin(),
+                    ),
+                    Field::Zmq => (
+                        "ZMQ",
+                        tick(self.cfg.options.zmq).to_owned(),
+                        target.includes_bitcoin(),
+                    ),
+                };
+                let focused = i == self.field;
+                let mut value_style = Style::default();
+                if !enabled {
+                    value_style = value_style.fg(Color::DarkGray);
+                }
+                if focused {
+                    value_style = value_style.add_modifier(Modifier::REVERSED);
+                }
+                Line::from(vec![
+                    Span::raw(if focused { "› " } else { "  " }),
+                    Span::styled(format!("{label:<14}"), Style::default().fg(Color::Gray)),
+                    Span::styled(format!(" {value} "), value_style),
+                ])
+            })
+            .collect();
+        lines.push(Line::from(vec![
+            Span::raw("  "),
+            Span::styled(format!("{:<14}", "Output Dir"), Style::default().fg(Color::Gray)),
+            Span::raw(format!(" {}", self.cfg.build_dir.display())),
+        ]));
+
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(" BitForge ")),
+            area,
+        );
+    }
+
+    fn render_progress(&self, frame: &mut Frame, area: Rect) {
+        let label = if self.paused {
+            format!("{:.0}% — ⏸ paused", self.progress * 100.0)
+        } else if let Some(silent_for) = self.stall {
+            format!(
+                "{:.0}% — ⏳ no output for {}",
+                self.progress * 100.0,
+                format_duration(silent_for.as_secs())
+            )
+        } else if self.is_busy() {
+            format!("{:.0}%", self.progress * 100.0)
+        } else {
+            "Idle".to_owned()
+        };
+        frame.render_widget(
+            Gauge::default()
+                .block(Block::default().borders(Borders::ALL).title(" Build Progress "))
+                .gauge_style(Style::default().fg(Color::Blue))
+                .ratio(f64::from(self.progress))
+                .label(label),
+            area,
+        );
+    }
+
+    fn render_log(&self, frame: &mut Frame, area: Rect) {
+        let height = area.height.saturating_sub(2) as usize;
+        let end = self.log.line_count().saturating_sub(self.scroll);
+        let start = end.saturating_sub(height);
+        let lines: Vec<Line> = (start..end)
+            .map(|i| styled_line(self.log.line(i), self.log.stderr_of(i).is_some()))
+            .collect();
+
+        let title = if self.scroll > 0 {
+            format!(" Build Log (↑{}) ", self.scroll)
+        } else {
+            " Build Log ".to_owned()
+        };
+        frame.render_widget(
+            Paragraph::new(lines)
+                .style(Style::default().fg(Color::Green))
+                .block(Block::default().borders(Borders::ALL).title(title)),
+            area,
+        );
+    }
+}
+
+/// One log line in the tool's own colours; unstyled text takes the line's
+/// severity colour, or the stderr colour for a `stderr` line.
+fn styled_line(line: &str, stderr: bool) -> Line<'_> {
+    let base = match classify(line) {
+        Some(Severity::Error) => Style::default().fg(Color::Red),
+        Some(Severity::Warning) => Style::default().fg(Color::Yellow),
+        None if stderr => Style::default().fg(Color::LightMagenta),
+        None => Style::default(),
+    };
+    let color = |c: ansi::Color| match c {
+        ansi::Color::Indexed(i) => Color::Indexed(i),
+        ansi::Color::Rgb(r, g, b) => Color::Rgb(r, g, b),
+    };
+    let spans = ansi::spans(line).into_iter().map(|(text, s)| {
+        let mut style = base;
+        if let Some(fg) = s.fg {
+            style = style.fg(color(fg));
+        }
+        if let Some(bg) = s.bg {
+            style = style.bg(color(bg));
+        }
+        for (on, modifier) in [
+            (s.bold, Modifier::BOLD),
+            (s.italic, Modifier::ITALIC),
+            (s.underline, Modifier::UNDERLINED),
+        ] {
+            if on {
+                style = style.add_modifier(modifier);
+            }
+        }
+        Span::styled(text, style)
+    });
+    Line::from(spans.collect::<Vec<_>>())
+}
+
+/// Index of `wanted` in `list`, else the newest (first) entry.
+fn preferred_index(list: &[String], wanted: Option<&str>) -> usize {
+    wanted
+        .and_then(|w| list.iter().position(|v| v == w))
+        .unwrap_or(0)
+}
+
+fn render_modal(frame: &mut Frame, modal: &Modal) {
+    let area = frame.area();
+    let width = area.width.saturating_sub(8).min(72);
+
+    let (title, mut lines, footer) = match modal {
+        Modal::Alert {
+            title,
+            message,
+            is_error,
+        } => {
+            let (icon, color) = if *is_error {
+                ("⛔ Error", Color::Red)
+            } else {
+                ("✅ Done", Color::Green)
+            };
+            let mut lines = vec![Line::styled(icon, Style::default().fg(color)), Line::raw("")];
+            lines.extend(message.lines().map(|l| Line::raw(l.to_owned())));
+            (title, lines, "Enter: OK")
+        }
+        Modal::Confirm {
+            title,
+            message,
+            items,
+            cursor,
+            ..
+        } => {
+            let mut lines: Vec<Line> = message.lines().map(|l| Line::raw(l.to_owned())).collect();
+            if !items.is_empty() {
+                lines.push(Line::raw(""));
+            }
+            for (i, item) in items.iter().enumerate() {
+                let mark = if item.checked { "[x]" } else { "[ ]" };
+                let style = if i == *cursor {
+                    Style::default().add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::default()
+                };
+                lines.push(Line::from(vec![
+                    Span::styled(format!("{mark} {}", item.label), style),
+                    Span::styled(format!("  {}", item.detail), Style::default().fg(Color::Gray)),
+                ]));
+            }
+            let footer = if items.is_empty() {
+                "y/Enter: yes   n/Esc: no"
+            } else {
+                "↑/↓: move   space: toggle   y/Enter: install selected   n/Esc: cancel"
+            };
+            (title, lines, footer)
+        }
+    };
+    lines.push(Line::raw(""));
+    lines.push(Line::styled(footer, Style::default().fg(Color::DarkGray)));
+
+    let height = (lines.len() as u16 + 2).min(area.height);
+    let popup = Rect {
+        x: area.x + (area.width.saturating_sub(width)) / 2,
+        y: area.y + (area.height.saturating_sub(height)) / 2,
+        width,
+        height,
+    };
+    frame.render_widget(Clear, popup);
+    frame.render_widget(
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .block(Block::default().borders(Borders::ALL).title(format!(" {title} "))),
+        popup,
+    );
+}